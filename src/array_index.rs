@@ -0,0 +1,297 @@
+use build_report::BuildReport;
+use knn_results::KnnResults;
+use radius_neighbors::{Radii, RadiusNeighbors};
+use search_buffers::SearchBuffers;
+use std::convert::TryFrom;
+use std::path::Path;
+use vec_index::VecIndex;
+use FlannError;
+use Indexable;
+use Neighbor;
+use Parameters;
+use RadiusResult;
+use SearchParams;
+
+/// An index over points of a dimensionality `D` that is known at compile time.
+///
+/// This is like `Index`, but points are plain `[T; D]` arrays.
+pub struct ArrayIndex<T: Indexable + 'static, const D: usize> {
+    index: VecIndex<T>,
+}
+
+impl<T: Indexable, const D: usize> ArrayIndex<T, D> {
+    pub fn new<I>(points: I, parameters: Parameters) -> Result<Self, FlannError>
+    where
+        I: IntoIterator<Item = [T; D]>,
+    {
+        Ok(Self {
+            index: VecIndex::new(D, points, parameters)?,
+        })
+    }
+
+    /// Loads an index previously written with `save` for the given points.
+    ///
+    /// The points must be the same ones the index was saved with, since FLANN
+    /// does not store the dataset in the index file.
+    pub fn load<P, I>(path: P, points: I, parameters: Parameters) -> Result<Self, FlannError>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = [T; D]>,
+    {
+        Ok(Self {
+            index: VecIndex::load(path, D, points, parameters)?,
+        })
+    }
+
+    /// Saves the index to a file so that it can be restored with `load`.
+    ///
+    /// The points are not saved, so they must be kept separately.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FlannError> {
+        self.index.save(path)
+    }
+
+    /// Adds a point to the index.
+    pub fn add(&mut self, point: [T; D]) -> Result<(), FlannError> {
+        self.index.add(point.to_vec())
+    }
+
+    /// Adds multiple points to the index.
+    pub fn add_multiple<I>(&mut self, points: I) -> Result<(), FlannError>
+    where
+        I: IntoIterator<Item = [T; D]>,
+    {
+        self.index.add_many(points)
+    }
+
+    /// Get the point that corresponds to this index `idx`.
+    ///
+    /// Returns `None` if there is no such point or it was removed.
+    pub fn get(&self, idx: usize) -> Option<&[T; D]> {
+        self.index
+            .get(idx)
+            .and_then(|point| <&[T; D]>::try_from(point).ok())
+    }
+
+    /// Removes a point at index `idx`.
+    pub fn remove(&mut self, idx: usize) -> Result<(), FlannError> {
+        self.index.remove(idx)
+    }
+
+    /// Returns the parameters that the index uses.
+    pub fn parameters(&self) -> Parameters {
+        self.index.parameters()
+    }
+
+    /// Returns what FLANN reported when building the index, or `None` if
+    /// the index was loaded.
+    pub fn build_report(&self) -> Option<&BuildReport> {
+        self.index.build_report()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Performs a search to find only the closest neighbor.
+    pub fn find_nearest_neighbor(
+        &self,
+        point: &[T; D],
+    ) -> Result<Neighbor<T::ResultType>, FlannError> {
+        self.index.find_nearest_neighbor(point)
+    }
+
+    /// Performs k-NN search for `num` neighbors.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    pub fn find_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T; D],
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.index.find_nearest_neighbors(num, point)
+    }
+
+    /// Performs k-NN search for `num` neighbors using `params` instead of
+    /// the search parameters the index was built with.
+    pub fn find_nearest_neighbors_with_params(
+        &self,
+        num: usize,
+        point: &[T; D],
+        params: &SearchParams,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.index
+            .find_nearest_neighbors_with_params(num, point, params)
+    }
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    pub fn find_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T; D],
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.index.find_nearest_neighbors_radius(num, radius, point)
+    }
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance,
+    /// using `params` instead of the search parameters the index was built with.
+    pub fn find_nearest_neighbors_radius_with_params(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T; D],
+        params: &SearchParams,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.index
+            .find_nearest_neighbors_radius_with_params(num, radius, point, params)
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// and reports whether there were more.
+    pub fn find_within_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T; D],
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        self.index.find_within_radius(num, radius, point)
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// using `params` instead of the search parameters the index was built with.
+    pub fn find_within_radius_with_params(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T; D],
+        params: &SearchParams,
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        self.index
+            .find_within_radius_with_params(num, radius, point, params)
+    }
+
+    /// Performs a search for all neighbors within `radius` distance.
+    ///
+    /// The neighbors are sorted by closest to furthest.
+    pub fn find_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T; D],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.index.find_all_within_radius(radius, point)
+    }
+
+    /// Performs a search for all neighbors within `radius` distance using
+    /// `params` instead of the search parameters the index was built with.
+    pub fn find_all_within_radius_with_params(
+        &self,
+        radius: f32,
+        point: &[T; D],
+        params: &SearchParams,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.index
+            .find_all_within_radius_with_params(radius, point, params)
+    }
+
+    /// Performs a search for all neighbors within the radius of several points.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    pub fn find_many_within_radius<'r, R: Into<Radii<'r>>>(
+        &self,
+        radii: R,
+        points: &[[T; D]],
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError> {
+        self.index
+            .find_many_within_radius_flat(radii, points.as_flattened())
+    }
+
+    /// Performs a search for all neighbors within the radius of several points
+    /// on the rayon thread pool.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    #[cfg(feature = "rayon")]
+    pub fn par_find_many_within_radius<'r, R: Into<Radii<'r>>>(
+        &self,
+        radii: R,
+        points: &[[T; D]],
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError>
+    where
+        T: Sync,
+        T::ResultType: Send,
+    {
+        self.index
+            .par_find_many_within_radius_flat(radii, points.as_flattened())
+    }
+
+    /// Performs k-NN search on `num` neighbors for several points.
+    ///
+    /// If there are less points in the set than `num` it returns that many
+    /// neighbors for each point.
+    pub fn find_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[[T; D]],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        self.index
+            .find_many_nearest_neighbors_flat(num, points.as_flattened())
+    }
+
+    /// Performs k-NN search on `num` neighbors for several points using `params`
+    /// instead of the search parameters the index was built with.
+    pub fn find_many_nearest_neighbors_with_params(
+        &self,
+        num: usize,
+        points: &[[T; D]],
+        params: &SearchParams,
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        self.index
+            .find_many_nearest_neighbors_flat_with_params(num, points.as_flattened(), params)
+    }
+
+    /// Performs k-NN search for `k` neighbors for several points without allocating.
+    ///
+    /// See `SliceIndex::knn_search_into`.
+    pub fn knn_search_into(
+        &self,
+        points: &[[T; D]],
+        k: usize,
+        indices: &mut [usize],
+        distances: &mut [T::ResultType],
+    ) -> Result<usize, FlannError> {
+        self.index
+            .knn_search_into(points.as_flattened(), k, indices, distances)
+    }
+
+    /// Performs k-NN search for `k` neighbors for several points, reusing the
+    /// memory of `buffers` from previous searches.
+    pub fn knn_search_into_buffers(
+        &self,
+        points: &[[T; D]],
+        k: usize,
+        buffers: &mut SearchBuffers<T::ResultType>,
+    ) -> Result<(), FlannError> {
+        self.index
+            .knn_search_into_buffers(points.as_flattened(), k, buffers)
+    }
+
+    /// Performs k-NN search for `num` neighbors for several points on the rayon thread pool.
+    ///
+    /// See `SliceIndex::par_find_many_nearest_neighbors`.
+    #[cfg(feature = "rayon")]
+    pub fn par_find_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[[T; D]],
+    ) -> Result<KnnResults<T::ResultType>, FlannError>
+    where
+        T: Sync,
+        T::ResultType: Send,
+    {
+        self.index
+            .par_find_many_nearest_neighbors(num, points.as_flattened())
+    }
+}
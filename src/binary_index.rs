@@ -0,0 +1,183 @@
+use enums::{Algorithm, DistanceType};
+use vec_index::VecIndex;
+use FlannError;
+use Neighbor;
+use Parameters;
+
+/// How many candidates the LSH index is asked for per requested neighbor.
+const LSH_CANDIDATES_PER_NEIGHBOR: usize = 4;
+
+/// An index over packed binary descriptors like ORB, BRIEF or AKAZE that
+/// searches by Hamming distance.
+///
+/// The FLANN C bindings don't support Hamming distance, so with `Algorithm::Lsh`
+/// the LSH index only finds candidates, which are then ranked by their exact
+/// Hamming distance. With `Algorithm::Linear` every descriptor is compared,
+/// which gives exact results.
+pub struct BinaryIndex<const N: usize> {
+    descriptors: Vec<[u8; N]>,
+    lsh: Option<VecIndex<u8>>,
+}
+
+impl<const N: usize> BinaryIndex<N> {
+    /// Makes a new index from descriptors.
+    ///
+    /// `parameters.algorithm` must be `Algorithm::Lsh`, which is configured by
+    /// `table_number`, `key_size` and `multi_probe_level`, or `Algorithm::Linear`.
+    /// The distance is always Hamming, so `distance_type` is ignored.
+    pub fn new<I>(descriptors: I, parameters: Parameters) -> Result<Self, FlannError>
+    where
+        I: IntoIterator<Item = [u8; N]>,
+    {
+        let descriptors: Vec<[u8; N]> = descriptors.into_iter().collect();
+        if descriptors.is_empty() {
+            return Err(FlannError::ZeroInputPoints);
+        }
+        let lsh = match parameters.algorithm {
+            Algorithm::Lsh => Some(VecIndex::new(
+                N,
+                descriptors
+                    .iter()
+                    .map(|descriptor| descriptor.iter().cloned()),
+                Parameters {
+                    distance_type: DistanceType::Euclidean,
+                    ..parameters
+                },
+            )?),
+            Algorithm::Linear => None,
+            algorithm => return Err(FlannError::InvalidAlgorithm { algorithm }),
+        };
+        Ok(Self { descriptors, lsh })
+    }
+
+    /// Adds a descriptor to the index.
+    pub fn add(&mut self, descriptor: [u8; N]) -> Result<(), FlannError> {
+        if let Some(ref mut lsh) = self.lsh {
+            lsh.add(descriptor.to_vec())?;
+        }
+        self.descriptors.push(descriptor);
+        Ok(())
+    }
+
+    /// Adds multiple descriptors to the index.
+    pub fn add_many(&mut self, descriptors: &[[u8; N]]) -> Result<(), FlannError> {
+        if let Some(ref mut lsh) = self.lsh {
+            lsh.add_many(
+                descriptors
+                    .iter()
+                    .map(|descriptor| descriptor.iter().cloned()),
+            )?;
+        }
+        self.descriptors.extend_from_slice(descriptors);
+        Ok(())
+    }
+
+    /// Get the descriptor that corresponds to this index `idx`.
+    pub fn get(&self, idx: usize) -> Option<&[u8; N]> {
+        self.descriptors.get(idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.descriptors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.descriptors.is_empty()
+    }
+
+    /// How many candidates to ask the LSH index for to find `num` neighbors.
+    fn num_candidates(&self, num: usize) -> usize {
+        num.saturating_mul(LSH_CANDIDATES_PER_NEIGHBOR)
+            .min(self.len())
+    }
+
+    /// Performs a search to find only the closest neighbor.
+    pub fn find_nearest_neighbor(&self, descriptor: &[u8; N]) -> Result<Neighbor<u32>, FlannError> {
+        self.find_nearest_neighbors(1, descriptor)?
+            .next()
+            .ok_or(FlannError::SearchFailed)
+    }
+
+    /// Performs k-NN search for `num` neighbors.
+    /// If there are less descriptors in the set than `num` it returns that many neighbors.
+    ///
+    /// The returned iterator is sorted by closest to furthest.
+    pub fn find_nearest_neighbors(
+        &self,
+        num: usize,
+        descriptor: &[u8; N],
+    ) -> Result<impl Iterator<Item = Neighbor<u32>>, FlannError> {
+        let neighbors = match self.lsh {
+            Some(ref lsh) => {
+                let candidates = lsh
+                    .find_nearest_neighbors(self.num_candidates(num), descriptor)?
+                    .map(|candidate| candidate.index);
+                self.rank(num, descriptor, candidates)
+            }
+            None => self.rank(num, descriptor, 0..self.descriptors.len()),
+        };
+        Ok(neighbors.into_iter())
+    }
+
+    /// Performs k-NN search for `num` neighbors for several descriptors.
+    ///
+    /// If there are less descriptors in the set than `num` it returns that many
+    /// neighbors for each descriptor.
+    pub fn find_many_nearest_neighbors(
+        &self,
+        num: usize,
+        descriptors: &[[u8; N]],
+    ) -> Result<Vec<Vec<Neighbor<u32>>>, FlannError> {
+        match self.lsh {
+            Some(ref lsh) => {
+                let candidates = lsh.find_many_nearest_neighbors_flat(
+                    self.num_candidates(num),
+                    &descriptors.concat(),
+                )?;
+                let neighbors = descriptors
+                    .iter()
+                    .zip(&candidates)
+                    .map(|(descriptor, candidates)| {
+                        self.rank(
+                            num,
+                            descriptor,
+                            candidates.iter().map(|candidate| candidate.index),
+                        )
+                    })
+                    .collect();
+                Ok(neighbors)
+            }
+            None => Ok(descriptors
+                .iter()
+                .map(|descriptor| self.rank(num, descriptor, 0..self.descriptors.len()))
+                .collect()),
+        }
+    }
+
+    /// Returns the `num` candidates with the smallest Hamming distance to `descriptor`.
+    fn rank<I>(&self, num: usize, descriptor: &[u8; N], candidates: I) -> Vec<Neighbor<u32>>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut neighbors = candidates
+            .into_iter()
+            // FLANN fills missing results with -1.
+            .filter(|&index| index < self.descriptors.len())
+            .map(|index| Neighbor {
+                index,
+                distance: hamming_distance(descriptor, &self.descriptors[index]),
+            })
+            .collect::<Vec<_>>();
+        let key = |neighbor: &Neighbor<u32>| (neighbor.distance, neighbor.index);
+        if num < neighbors.len() {
+            neighbors.select_nth_unstable_by_key(num, key);
+            neighbors.truncate(num);
+        }
+        neighbors.sort_unstable_by_key(key);
+        neighbors
+    }
+}
+
+fn hamming_distance<const N: usize>(a: &[u8; N], b: &[u8; N]) -> u32 {
+    a.iter().zip(b).map(|(a, b)| (a ^ b).count_ones()).sum()
+}
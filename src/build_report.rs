@@ -0,0 +1,38 @@
+use enums::Algorithm;
+use std::time::Duration;
+use Parameters;
+
+/// What FLANN reported when building an index.
+///
+/// With `Algorithm::Autotuned`, FLANN picks the algorithm and its parameters
+/// itself. Those are kept in `parameters`, so they can be recorded and passed
+/// to later builds to skip autotuning.
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    /// How much faster searches are than linear search, which FLANN only
+    /// measures when autotuning.
+    pub speedup: Option<f32>,
+    /// The algorithm that was built.
+    pub algorithm: Algorithm,
+    /// The parameters the index was built with, including tuned ones.
+    pub parameters: Parameters,
+    /// How long FLANN took to build the index.
+    pub duration: Duration,
+}
+
+impl BuildReport {
+    pub(crate) fn new(
+        requested: Algorithm,
+        speedup: f32,
+        parameters: Parameters,
+        duration: Duration,
+    ) -> Self {
+        let autotuned = matches!(requested, Algorithm::Autotuned);
+        Self {
+            speedup: if autotuned { Some(speedup) } else { None },
+            algorithm: parameters.algorithm,
+            parameters,
+            duration,
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! A self-contained file format for owned indices.
+//!
+//! A bundle stores everything needed to restore an index in one file:
+//!
+//! * the magic bytes `FLANNBDL` and the format version
+//! * a byte order mark and the widths of `usize` and `c_long`, since FLANN
+//!   writes its index in native layout
+//! * the FLANN data type tag of the elements
+//! * `point_len`, the number of points and the `Parameters`, including the distance
+//! * the flat dataset
+//! * the number of removed points followed by their ids
+//! * the length of the FLANN index followed by the index itself
+//!
+//! All numbers are written in native byte order.
+
+use raw;
+use saved_index::TempFile;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::mem::{size_of, size_of_val};
+use std::os::raw::c_long;
+use std::path::Path;
+use DistanceType;
+use FlannError;
+use Indexable;
+use Parameters;
+
+const MAGIC: &[u8; 8] = b"FLANNBDL";
+const VERSION: u32 = 3;
+/// Bundles before the distance was stored, which always used the default distance.
+const VERSION_WITHOUT_DISTANCE: u32 = 1;
+/// Bundles before removed points were stored, which lost their removals.
+const VERSION_WITHOUT_REMOVED: u32 = 2;
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+pub(crate) struct Bundle<T> {
+    pub point_len: usize,
+    pub parameters: Parameters,
+    pub points: Vec<T>,
+    /// The ids of the points that were removed.
+    pub removed: Vec<usize>,
+    /// The FLANN index, staged in a temporary file so that FLANN can load it.
+    pub index: TempFile,
+}
+
+pub(crate) fn write<T: Indexable, W: Write>(
+    writer: &mut W,
+    point_len: usize,
+    parameters: &Parameters,
+    storage: &[Vec<T>],
+    removed: &[usize],
+    index: &Path,
+) -> Result<(), FlannError> {
+    write_inner(writer, point_len, parameters, storage, removed, index)
+        .map_err(|_| FlannError::FailedToWriteBundle)
+}
+
+fn write_inner<T: Indexable, W: Write>(
+    writer: &mut W,
+    point_len: usize,
+    parameters: &Parameters,
+    storage: &[Vec<T>],
+    removed: &[usize],
+    index: &Path,
+) -> io::Result<()> {
+    let num_points = storage.iter().map(|points| points.len()).sum::<usize>() / point_len;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_ne_bytes())?;
+    writer.write_all(&BYTE_ORDER_MARK.to_ne_bytes())?;
+    writer.write_all(&(size_of::<usize>() as u32).to_ne_bytes())?;
+    writer.write_all(&(size_of::<c_long>() as u32).to_ne_bytes())?;
+    writer.write_all(&T::DATA_TYPE.to_ne_bytes())?;
+    writer.write_all(&(point_len as u64).to_ne_bytes())?;
+    writer.write_all(&(num_points as u64).to_ne_bytes())?;
+    write_parameters(writer, parameters)?;
+    for points in storage {
+        writer.write_all(as_bytes(points))?;
+    }
+    writer.write_all(&(removed.len() as u64).to_ne_bytes())?;
+    for &id in removed {
+        writer.write_all(&(id as u64).to_ne_bytes())?;
+    }
+    let mut index = File::open(index)?;
+    writer.write_all(&index.metadata()?.len().to_ne_bytes())?;
+    io::copy(&mut index, writer)?;
+    writer.flush()
+}
+
+fn write_parameters<W: Write>(writer: &mut W, parameters: &Parameters) -> io::Result<()> {
+    let p: raw::FLANNParameters = parameters.into();
+    writer.write_all(&p.algorithm.to_ne_bytes())?;
+    writer.write_all(&p.checks.to_ne_bytes())?;
+    writer.write_all(&p.eps.to_ne_bytes())?;
+    writer.write_all(&p.sorted.to_ne_bytes())?;
+    writer.write_all(&p.max_neighbors.to_ne_bytes())?;
+    writer.write_all(&p.cores.to_ne_bytes())?;
+    writer.write_all(&p.trees.to_ne_bytes())?;
+    writer.write_all(&p.leaf_max_size.to_ne_bytes())?;
+    writer.write_all(&p.branching.to_ne_bytes())?;
+    writer.write_all(&p.iterations.to_ne_bytes())?;
+    writer.write_all(&p.centers_init.to_ne_bytes())?;
+    writer.write_all(&p.cb_index.to_ne_bytes())?;
+    writer.write_all(&p.target_precision.to_ne_bytes())?;
+    writer.write_all(&p.build_weight.to_ne_bytes())?;
+    writer.write_all(&p.memory_weight.to_ne_bytes())?;
+    writer.write_all(&p.sample_fraction.to_ne_bytes())?;
+    writer.write_all(&p.table_number_.to_ne_bytes())?;
+    writer.write_all(&p.key_size_.to_ne_bytes())?;
+    writer.write_all(&p.multi_probe_level_.to_ne_bytes())?;
+    writer.write_all(&p.log_level.to_ne_bytes())?;
+    writer.write_all(&p.random_seed.to_ne_bytes())?;
+    writer.write_all(&parameters.rebuild_threshold.to_ne_bytes())?;
+    writer.write_all(&parameters.distance_type.as_raw().to_ne_bytes())?;
+    writer.write_all(&parameters.distance_order.to_ne_bytes())
+}
+
+/// Reads a bundle from `r`, which must not contain more than `max_len` bytes.
+pub(crate) fn read<T: Indexable, R: Read>(
+    r: &mut R,
+    max_len: u64,
+) -> Result<Bundle<T>, FlannError> {
+    let mut magic = [0u8; 8];
+    read_exact(r, &mut magic)?;
+    if &magic != MAGIC {
+        return Err(FlannError::InvalidBundle);
+    }
+    let version = u32::from_ne_bytes(read_array(r)?);
+    if version != VERSION
+        && version != VERSION_WITHOUT_REMOVED
+        && version != VERSION_WITHOUT_DISTANCE
+    {
+        return Err(FlannError::UnsupportedBundleVersion { version });
+    }
+    if u32::from_ne_bytes(read_array(r)?) != BYTE_ORDER_MARK
+        || u32::from_ne_bytes(read_array(r)?) != size_of::<usize>() as u32
+        || u32::from_ne_bytes(read_array(r)?) != size_of::<c_long>() as u32
+    {
+        return Err(FlannError::IncompatibleBundle);
+    }
+    let data_type = i32::from_ne_bytes(read_array(r)?);
+    if data_type != T::DATA_TYPE {
+        return Err(FlannError::BundleDataTypeMismatch {
+            expected: T::DATA_TYPE,
+            got: data_type,
+        });
+    }
+    let point_len = u64::from_ne_bytes(read_array(r)?);
+    let num_points = u64::from_ne_bytes(read_array(r)?);
+    if point_len == 0 {
+        return Err(FlannError::InvalidBundle);
+    }
+    let parameters = read_parameters(r, version)?;
+
+    // Check the size against the file before allocating so corrupted sizes can't exhaust memory.
+    let points_len = point_len
+        .checked_mul(num_points)
+        .ok_or(FlannError::InvalidBundle)?;
+    let points_bytes = points_len
+        .checked_mul(size_of::<T>() as u64)
+        .ok_or(FlannError::InvalidBundle)?;
+    if points_bytes > max_len {
+        return Err(FlannError::InvalidBundle);
+    }
+    let mut points = vec![T::default(); points_len as usize];
+    read_exact(r, as_bytes_mut(&mut points))?;
+
+    let mut removed = Vec::new();
+    if version >= VERSION {
+        let removed_len = u64::from_ne_bytes(read_array(r)?);
+        if removed_len > num_points {
+            return Err(FlannError::InvalidBundle);
+        }
+        removed.reserve(removed_len as usize);
+        for _ in 0..removed_len {
+            let id = u64::from_ne_bytes(read_array(r)?);
+            if id >= num_points {
+                return Err(FlannError::InvalidBundle);
+            }
+            removed.push(id as usize);
+        }
+    }
+
+    let index_len = u64::from_ne_bytes(read_array(r)?);
+    let (index, mut index_file) = TempFile::new().map_err(|_| FlannError::FailedToReadBundle)?;
+    let copied = io::copy(&mut r.take(index_len), &mut index_file)
+        .map_err(|_| FlannError::FailedToReadBundle)?;
+    if copied != index_len {
+        return Err(FlannError::InvalidBundle);
+    }
+
+    Ok(Bundle {
+        point_len: point_len as usize,
+        parameters,
+        points,
+        removed,
+        index,
+    })
+}
+
+fn read_parameters<R: Read>(r: &mut R, version: u32) -> Result<Parameters, FlannError> {
+    let p = raw::FLANNParameters {
+        algorithm: u32::from_ne_bytes(read_array(r)?),
+        checks: i32::from_ne_bytes(read_array(r)?),
+        eps: f32::from_ne_bytes(read_array(r)?),
+        sorted: i32::from_ne_bytes(read_array(r)?),
+        max_neighbors: i32::from_ne_bytes(read_array(r)?),
+        cores: i32::from_ne_bytes(read_array(r)?),
+        trees: i32::from_ne_bytes(read_array(r)?),
+        leaf_max_size: i32::from_ne_bytes(read_array(r)?),
+        branching: i32::from_ne_bytes(read_array(r)?),
+        iterations: i32::from_ne_bytes(read_array(r)?),
+        centers_init: u32::from_ne_bytes(read_array(r)?),
+        cb_index: f32::from_ne_bytes(read_array(r)?),
+        target_precision: f32::from_ne_bytes(read_array(r)?),
+        build_weight: f32::from_ne_bytes(read_array(r)?),
+        memory_weight: f32::from_ne_bytes(read_array(r)?),
+        sample_fraction: f32::from_ne_bytes(read_array(r)?),
+        table_number_: u32::from_ne_bytes(read_array(r)?),
+        key_size_: u32::from_ne_bytes(read_array(r)?),
+        multi_probe_level_: u32::from_ne_bytes(read_array(r)?),
+        log_level: u32::from_ne_bytes(read_array(r)?),
+        random_seed: c_long::from_ne_bytes(read_array(r)?),
+    };
+    let mut parameters = Parameters::from_raw(p).map_err(|_| FlannError::InvalidBundle)?;
+    parameters.rebuild_threshold = f32::from_ne_bytes(read_array(r)?);
+    if version == VERSION_WITHOUT_DISTANCE {
+        return Ok(parameters);
+    }
+    parameters.distance_type = DistanceType::from_raw(u32::from_ne_bytes(read_array(r)?))
+        .ok_or(FlannError::InvalidBundle)?;
+    parameters.distance_order = i32::from_ne_bytes(read_array(r)?);
+    Ok(parameters)
+}
+
+pub(crate) fn read_exact<R: Read>(r: &mut R, buf: &mut [u8]) -> Result<(), FlannError> {
+    r.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => FlannError::InvalidBundle,
+        _ => FlannError::FailedToReadBundle,
+    })
+}
+
+pub(crate) fn read_array<R: Read, const N: usize>(r: &mut R) -> Result<[u8; N], FlannError> {
+    let mut buf = [0u8; N];
+    read_exact(r, &mut buf)?;
+    Ok(buf)
+}
+
+fn as_bytes<T: Indexable>(points: &[T]) -> &[u8] {
+    // Indexable is only implemented for plain numeric types.
+    unsafe { std::slice::from_raw_parts(points.as_ptr() as *const u8, size_of_val(points)) }
+}
+
+fn as_bytes_mut<T: Indexable>(points: &mut [T]) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(points.as_mut_ptr() as *mut u8, size_of_val(points)) }
+}
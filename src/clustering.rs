@@ -0,0 +1,77 @@
+use config::KMeansParams;
+use distance::Distance;
+use generic_array::{ArrayLength, GenericArray};
+use FlannError;
+use Indexable;
+use Parameters;
+
+/// Clusters points with hierarchical k-means and returns the cluster centers.
+///
+/// The points must be in a slice of memory in component order where there
+/// are `point_len` components. The centers are returned the same way.
+///
+/// Clustering is controlled by `branching`, `iterations`, `centers_init`,
+/// `cb_index` and the distance in `parameters`. FLANN only produces numbers
+/// of clusters of the form `(branching - 1) * k + 1`, so it rounds
+/// `num_clusters` down to the closest such number. The actual number of clusters produced is
+/// `centers.len() / point_len`.
+pub fn compute_cluster_centers<T: Indexable>(
+    point_len: usize,
+    points: &[T],
+    num_clusters: usize,
+    parameters: &Parameters,
+) -> Result<Vec<T::ResultType>, FlannError> {
+    if points.is_empty() {
+        return Err(FlannError::ZeroInputPoints);
+    }
+    if points.len() % point_len != 0 {
+        return Err(FlannError::InvalidFlatPointsLen {
+            expected: point_len,
+            got: points.len(),
+        });
+    }
+    if num_clusters == 0 {
+        return Err(FlannError::ZeroClusters);
+    }
+    KMeansParams::from(parameters).validate()?;
+    let distance = Distance::from_parameters(parameters)?;
+    let mut flann_params = parameters.into();
+    let mut centers = vec![T::ResultType::default(); num_clusters * point_len];
+    let retval = distance.with(|| unsafe {
+        T::compute_cluster_centers(
+            points.as_ptr() as *mut T,
+            (points.len() / point_len) as i32,
+            point_len as i32,
+            num_clusters as i32,
+            centers.as_mut_ptr(),
+            &mut flann_params,
+        )
+    });
+    if retval <= 0 {
+        return Err(FlannError::ClusteringFailed);
+    }
+    centers.truncate(retval as usize * point_len);
+    Ok(centers)
+}
+
+/// Clusters points with hierarchical k-means and returns the cluster centers.
+///
+/// See `compute_cluster_centers` for details.
+pub fn compute_cluster_centers_array<T, N>(
+    points: &[GenericArray<T, N>],
+    num_clusters: usize,
+    parameters: &Parameters,
+) -> Result<Vec<GenericArray<T::ResultType, N>>, FlannError>
+where
+    T: Indexable,
+    N: ArrayLength<T> + ArrayLength<T::ResultType>,
+{
+    let points = unsafe {
+        std::slice::from_raw_parts(points.as_ptr() as *const T, points.len() * N::to_usize())
+    };
+    let centers = compute_cluster_centers(N::to_usize(), points, num_clusters, parameters)?;
+    Ok(centers
+        .chunks(N::to_usize())
+        .map(GenericArray::clone_from_slice)
+        .collect())
+}
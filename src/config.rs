@@ -0,0 +1,380 @@
+//! Typed configurations for the FLANN algorithms.
+//!
+//! `Parameters` has a field for every algorithm, and FLANN silently ignores
+//! the fields that don't belong to the chosen one. An `IndexConfig` only has
+//! the fields of its algorithm and checks their ranges.
+
+use enums::{Algorithm, CentersInit};
+use raw;
+use FlannError;
+use Parameters;
+
+/// Randomized kd-trees searched in parallel.
+#[derive(Clone, Copy, Debug)]
+pub struct KDTreeParams {
+    /// The number of randomized trees, at least 1.
+    pub trees: i32,
+}
+
+/// A single kd-tree, which works best for low dimensional data.
+#[derive(Clone, Copy, Debug)]
+pub struct KDTreeSingleParams {
+    /// The maximum number of points in a leaf, at least 1.
+    pub leaf_max_size: i32,
+}
+
+/// A hierarchical k-means tree.
+#[derive(Clone, Copy, Debug)]
+pub struct KMeansParams {
+    /// The number of children of every node, at least 2.
+    pub branching: i32,
+    /// The number of k-means iterations per node, or -1 to run until convergence.
+    pub iterations: i32,
+    pub centers_init: CentersInit,
+    /// The cluster boundary index used when searching, not negative.
+    pub cb_index: f32,
+}
+
+/// Randomized kd-trees combined with a hierarchical k-means tree.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompositeParams {
+    pub kdtree: KDTreeParams,
+    pub kmeans: KMeansParams,
+}
+
+/// Hierarchical clustering trees, which work with any distance.
+#[derive(Clone, Copy, Debug)]
+pub struct HierarchicalParams {
+    /// The number of children of every node, at least 2.
+    pub branching: i32,
+    pub centers_init: CentersInit,
+    /// The number of parallel trees, at least 1.
+    pub trees: i32,
+    /// The maximum number of points in a leaf, at least 1.
+    pub leaf_max_size: i32,
+}
+
+/// Multi-probe locality sensitive hashing for binary features.
+#[derive(Clone, Copy, Debug)]
+pub struct LshParams {
+    /// The number of hash tables, at least 1.
+    pub table_number: u32,
+    /// The length of the hash keys in bits, from 1 to 32.
+    pub key_size: u32,
+    /// How many neighboring buckets are probed, 0 for standard LSH.
+    pub multi_probe_level: u32,
+}
+
+/// Lets FLANN choose the algorithm and its parameters for the dataset.
+#[derive(Clone, Copy, Debug)]
+pub struct AutotunedParams {
+    /// The fraction of true nearest neighbors that searches should find, in `(0, 1]`.
+    pub target_precision: f32,
+    /// The importance of build time compared to search time, not negative.
+    pub build_weight: f32,
+    /// The importance of memory use compared to time, not negative.
+    pub memory_weight: f32,
+    /// The fraction of the dataset used for tuning, in `(0, 1]`.
+    pub sample_fraction: f32,
+}
+
+/// The algorithm of an index together with its parameters.
+#[derive(Clone, Copy, Debug)]
+pub enum IndexConfig {
+    Linear,
+    KDTree(KDTreeParams),
+    KDTreeSingle(KDTreeSingleParams),
+    KMeans(KMeansParams),
+    Composite(CompositeParams),
+    Hierarchical(HierarchicalParams),
+    Lsh(LshParams),
+    Autotuned(AutotunedParams),
+}
+
+impl Default for KDTreeParams {
+    fn default() -> KDTreeParams {
+        let p = Parameters::default();
+        KDTreeParams { trees: p.trees }
+    }
+}
+
+impl Default for KDTreeSingleParams {
+    fn default() -> KDTreeSingleParams {
+        let p = Parameters::default();
+        KDTreeSingleParams {
+            leaf_max_size: p.leaf_max_size,
+        }
+    }
+}
+
+impl Default for KMeansParams {
+    fn default() -> KMeansParams {
+        (&Parameters::default()).into()
+    }
+}
+
+impl<'a> From<&'a Parameters> for KMeansParams {
+    fn from(p: &'a Parameters) -> KMeansParams {
+        KMeansParams {
+            branching: p.branching,
+            iterations: p.iterations,
+            centers_init: p.centers_init,
+            cb_index: p.cb_index,
+        }
+    }
+}
+
+impl Default for HierarchicalParams {
+    fn default() -> HierarchicalParams {
+        let p = Parameters::default();
+        HierarchicalParams {
+            branching: p.branching,
+            centers_init: p.centers_init,
+            trees: p.trees,
+            leaf_max_size: p.leaf_max_size,
+        }
+    }
+}
+
+impl Default for LshParams {
+    fn default() -> LshParams {
+        let p = Parameters::default();
+        LshParams {
+            table_number: p.table_number,
+            key_size: p.key_size,
+            multi_probe_level: p.multi_probe_level,
+        }
+    }
+}
+
+impl Default for AutotunedParams {
+    fn default() -> AutotunedParams {
+        let p = Parameters::default();
+        AutotunedParams {
+            target_precision: p.target_precision,
+            build_weight: p.build_weight,
+            memory_weight: p.memory_weight,
+            sample_fraction: p.sample_fraction,
+        }
+    }
+}
+
+fn check(
+    valid: bool,
+    parameter: &'static str,
+    requirement: &'static str,
+) -> Result<(), FlannError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(FlannError::InvalidParameter {
+            parameter,
+            requirement,
+        })
+    }
+}
+
+impl KDTreeParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        check(self.trees > 0, "trees", "must be at least 1")
+    }
+}
+
+impl KDTreeSingleParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        check(
+            self.leaf_max_size > 0,
+            "leaf_max_size",
+            "must be at least 1",
+        )
+    }
+}
+
+impl KMeansParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        check(self.branching >= 2, "branching", "must be at least 2")?;
+        check(
+            self.iterations >= -1,
+            "iterations",
+            "must be -1 or at least 0",
+        )?;
+        check(self.cb_index >= 0.0, "cb_index", "must not be negative")
+    }
+}
+
+impl CompositeParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        self.kdtree.validate()?;
+        self.kmeans.validate()
+    }
+}
+
+impl HierarchicalParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        check(self.branching >= 2, "branching", "must be at least 2")?;
+        check(self.trees > 0, "trees", "must be at least 1")?;
+        check(
+            self.leaf_max_size > 0,
+            "leaf_max_size",
+            "must be at least 1",
+        )
+    }
+}
+
+impl LshParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        check(self.table_number > 0, "table_number", "must be at least 1")?;
+        check(
+            self.key_size > 0 && self.key_size <= 32,
+            "key_size",
+            "must be between 1 and 32",
+        )
+    }
+}
+
+impl AutotunedParams {
+    pub fn validate(&self) -> Result<(), FlannError> {
+        check(
+            self.target_precision > 0.0 && self.target_precision <= 1.0,
+            "target_precision",
+            "must be greater than 0 and at most 1",
+        )?;
+        check(
+            self.build_weight >= 0.0,
+            "build_weight",
+            "must not be negative",
+        )?;
+        check(
+            self.memory_weight >= 0.0,
+            "memory_weight",
+            "must not be negative",
+        )?;
+        check(
+            self.sample_fraction > 0.0 && self.sample_fraction <= 1.0,
+            "sample_fraction",
+            "must be greater than 0 and at most 1",
+        )
+    }
+}
+
+impl IndexConfig {
+    pub fn algorithm(&self) -> Algorithm {
+        match *self {
+            IndexConfig::Linear => Algorithm::Linear,
+            IndexConfig::KDTree(_) => Algorithm::KDTree,
+            IndexConfig::KDTreeSingle(_) => Algorithm::KDTreeSingle,
+            IndexConfig::KMeans(_) => Algorithm::KMeans,
+            IndexConfig::Composite(_) => Algorithm::Composite,
+            IndexConfig::Hierarchical(_) => Algorithm::Hierarchical,
+            IndexConfig::Lsh(_) => Algorithm::Lsh,
+            IndexConfig::Autotuned(_) => Algorithm::Autotuned,
+        }
+    }
+
+    /// Checks that the parameters are in the ranges the algorithm accepts.
+    pub fn validate(&self) -> Result<(), FlannError> {
+        match *self {
+            IndexConfig::Linear => Ok(()),
+            IndexConfig::KDTree(ref p) => p.validate(),
+            IndexConfig::KDTreeSingle(ref p) => p.validate(),
+            IndexConfig::KMeans(ref p) => p.validate(),
+            IndexConfig::Composite(ref p) => p.validate(),
+            IndexConfig::Hierarchical(ref p) => p.validate(),
+            IndexConfig::Lsh(ref p) => p.validate(),
+            IndexConfig::Autotuned(ref p) => p.validate(),
+        }
+    }
+
+    /// Sets the algorithm and its fields in `parameters`, leaving the others as they are.
+    pub fn apply(&self, parameters: &mut Parameters) {
+        parameters.algorithm = self.algorithm();
+        match *self {
+            IndexConfig::Linear => {}
+            IndexConfig::KDTree(p) => apply_kdtree(p, parameters),
+            IndexConfig::KDTreeSingle(p) => parameters.leaf_max_size = p.leaf_max_size,
+            IndexConfig::KMeans(p) => apply_kmeans(p, parameters),
+            IndexConfig::Composite(p) => {
+                apply_kdtree(p.kdtree, parameters);
+                apply_kmeans(p.kmeans, parameters);
+            }
+            IndexConfig::Hierarchical(p) => {
+                parameters.branching = p.branching;
+                parameters.centers_init = p.centers_init;
+                parameters.trees = p.trees;
+                parameters.leaf_max_size = p.leaf_max_size;
+            }
+            IndexConfig::Lsh(p) => {
+                parameters.table_number = p.table_number;
+                parameters.key_size = p.key_size;
+                parameters.multi_probe_level = p.multi_probe_level;
+            }
+            IndexConfig::Autotuned(p) => {
+                parameters.target_precision = p.target_precision;
+                parameters.build_weight = p.build_weight;
+                parameters.memory_weight = p.memory_weight;
+                parameters.sample_fraction = p.sample_fraction;
+            }
+        }
+    }
+
+    /// Reads the config of the algorithm set in `parameters`.
+    ///
+    /// Returns `None` for `Algorithm::Saved`, which has no config.
+    pub fn from_parameters(parameters: &Parameters) -> Option<IndexConfig> {
+        let p = parameters;
+        let kdtree = KDTreeParams { trees: p.trees };
+        let kmeans = KMeansParams::from(p);
+        Some(match p.algorithm {
+            Algorithm::Linear => IndexConfig::Linear,
+            Algorithm::KDTree => IndexConfig::KDTree(kdtree),
+            Algorithm::KDTreeSingle => IndexConfig::KDTreeSingle(KDTreeSingleParams {
+                leaf_max_size: p.leaf_max_size,
+            }),
+            Algorithm::KMeans => IndexConfig::KMeans(kmeans),
+            Algorithm::Composite => IndexConfig::Composite(CompositeParams { kdtree, kmeans }),
+            Algorithm::Hierarchical => IndexConfig::Hierarchical(HierarchicalParams {
+                branching: p.branching,
+                centers_init: p.centers_init,
+                trees: p.trees,
+                leaf_max_size: p.leaf_max_size,
+            }),
+            Algorithm::Lsh => IndexConfig::Lsh(LshParams {
+                table_number: p.table_number,
+                key_size: p.key_size,
+                multi_probe_level: p.multi_probe_level,
+            }),
+            Algorithm::Autotuned => IndexConfig::Autotuned(AutotunedParams {
+                target_precision: p.target_precision,
+                build_weight: p.build_weight,
+                memory_weight: p.memory_weight,
+                sample_fraction: p.sample_fraction,
+            }),
+            Algorithm::Saved => return None,
+        })
+    }
+}
+
+fn apply_kdtree(p: KDTreeParams, parameters: &mut Parameters) {
+    parameters.trees = p.trees;
+}
+
+fn apply_kmeans(p: KMeansParams, parameters: &mut Parameters) {
+    parameters.branching = p.branching;
+    parameters.iterations = p.iterations;
+    parameters.centers_init = p.centers_init;
+    parameters.cb_index = p.cb_index;
+}
+
+impl From<IndexConfig> for Parameters {
+    fn from(config: IndexConfig) -> Parameters {
+        let mut parameters = Parameters::default();
+        config.apply(&mut parameters);
+        parameters
+    }
+}
+
+impl<'a> From<&'a IndexConfig> for raw::FLANNParameters {
+    fn from(config: &'a IndexConfig) -> raw::FLANNParameters {
+        (&Parameters::from(*config)).into()
+    }
+}
@@ -0,0 +1,73 @@
+use enums::DistanceType;
+use raw;
+use std::os::raw::c_int;
+use std::sync::{PoisonError, RwLock};
+use FlannError;
+use Parameters;
+
+/// The distance FLANN's global setting was last set to by this crate.
+static CURRENT: RwLock<Option<(raw::flann_distance_t, c_int)>> = RwLock::new(None);
+
+/// The distance an index is built and used with.
+///
+/// FLANN selects the distance for every call from a process-global setting,
+/// and an index must only ever be used with the distance it was built with.
+/// `with` sets the global distance and keeps it locked while FLANN runs, so
+/// calls with the same distance can run concurrently while calls with
+/// different distances wait for each other.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Distance {
+    pub distance_type: DistanceType,
+    pub order: c_int,
+}
+
+impl Distance {
+    pub fn new(distance_type: DistanceType, order: i32) -> Result<Self, FlannError> {
+        match distance_type {
+            DistanceType::Euclidean
+            | DistanceType::L2
+            | DistanceType::Manhattan
+            | DistanceType::L1
+            | DistanceType::HistIntersect
+            | DistanceType::Hellinger
+            | DistanceType::ChiSquare
+            | DistanceType::KullbackLeibler => Ok(Self {
+                distance_type,
+                order: 0,
+            }),
+            // FLANN doesn't check the order and computes garbage for orders below 1.
+            DistanceType::Minkowski if order < 1 => Err(FlannError::InvalidParameter {
+                parameter: "distance_order",
+                requirement: "must be at least 1 for Minkowski distance",
+            }),
+            DistanceType::Minkowski => Ok(Self {
+                distance_type,
+                order,
+            }),
+            // The C bindings log an error and fail for everything else.
+            _ => Err(FlannError::UnsupportedDistanceType { distance_type }),
+        }
+    }
+
+    pub fn from_parameters(parameters: &Parameters) -> Result<Self, FlannError> {
+        Self::new(parameters.distance_type, parameters.distance_order)
+    }
+
+    /// Runs `f`, which calls FLANN, with FLANN's global distance set to this one.
+    pub fn with<R, F: FnOnce() -> R>(self, f: F) -> R {
+        let key = Some((self.distance_type.as_raw(), self.order));
+        let _guard = loop {
+            let current = CURRENT.read().unwrap_or_else(PoisonError::into_inner);
+            if *current == key {
+                break current;
+            }
+            drop(current);
+            let mut current = CURRENT.write().unwrap_or_else(PoisonError::into_inner);
+            if *current != key {
+                unsafe { raw::flann_set_distance_type(self.distance_type.as_raw(), self.order) };
+                *current = key;
+            }
+        };
+        f()
+    }
+}
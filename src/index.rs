@@ -1,25 +1,44 @@
+use build_report::BuildReport;
+use distance::Distance;
 use generic_array::{ArrayLength, GenericArray};
-use itertools::{IntoChunks, Itertools};
+use knn_results::KnnResults;
+use radius_neighbors::{Radii, RadiusNeighbors};
 use raw;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use saved_index;
+use slice_index::MIN_RADIUS_BUFFER_LEN;
+#[cfg(feature = "rayon")]
+use slice_index::PARALLEL_CHUNK_LEN;
 use std::marker::PhantomData;
+use std::path::Path;
+use std::time::Instant;
 use FlannError;
 use Indexable;
 use Neighbor;
 use Parameters;
+use RadiusResult;
+use SearchParams;
 
 pub struct Index<T: Indexable, N: ArrayLength<T>> {
     index: raw::flann_index_t,
     storage: Vec<Vec<T>>,
     parameters: raw::FLANNParameters,
     rebuild_threshold: f32,
+    distance: Distance,
+    build_report: Option<BuildReport>,
     _phantom: PhantomData<(T, N)>,
 }
 
+// See the corresponding impls for `SliceIndex`.
+unsafe impl<T: Indexable + Send, N: ArrayLength<T>> Send for Index<T, N> {}
+unsafe impl<T: Indexable + Sync, N: ArrayLength<T>> Sync for Index<T, N> {}
+
 impl<T: Indexable, N: ArrayLength<T>> Drop for Index<T, N> {
     fn drop(&mut self) {
-        unsafe {
+        self.distance.with(|| unsafe {
             T::free_index(self.index, &mut self.parameters);
-        }
+        })
     }
 }
 
@@ -32,10 +51,14 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
         if points_vec.is_empty() {
             return Err(FlannError::ZeroInputPoints);
         }
+        parameters.validate()?;
         let mut speedup = 0.0;
+        let distance = Distance::from_parameters(&parameters)?;
         let rebuild_threshold = parameters.rebuild_threshold;
+        let requested = parameters.algorithm;
         let mut flann_params = parameters.into();
-        let index = unsafe {
+        let start = Instant::now();
+        let index = distance.with(|| unsafe {
             T::build_index(
                 points_vec.as_ptr() as *mut T,
                 (points_vec.len() / N::to_usize()) as i32,
@@ -43,23 +66,88 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
                 &mut speedup,
                 &mut flann_params,
             )
-        };
+        });
+        let duration = start.elapsed();
         if index.is_null() {
             return Err(FlannError::FailedToBuildIndex);
         }
-        Ok(Self {
+        let mut index = Self {
             index,
             storage: vec![points_vec],
             parameters: flann_params,
             rebuild_threshold,
+            distance,
+            build_report: None,
+            _phantom: PhantomData,
+        };
+        index.build_report = Some(BuildReport::new(
+            requested,
+            speedup,
+            index.parameters(),
+            duration,
+        ));
+        Ok(index)
+    }
+
+    /// Loads an index previously written with `save` for the given points.
+    ///
+    /// The points must be the same ones the index was saved with, since FLANN
+    /// does not store the dataset in the index file.
+    pub fn load<P, I>(path: P, points: I, mut parameters: Parameters) -> Result<Self, FlannError>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = GenericArray<T, N>>,
+    {
+        let points_vec: Vec<T> = points.into_iter().flat_map(|p| p.into_iter()).collect();
+        if points_vec.is_empty() {
+            return Err(FlannError::ZeroInputPoints);
+        }
+        let shape = saved_index::read_shape(&path)?;
+        shape.check(N::to_usize(), points_vec.len())?;
+        shape.check_parameters(&mut parameters)?;
+        let filename = saved_index::path_to_cstring(&path)?;
+        let distance = Distance::from_parameters(&parameters)?;
+        let rebuild_threshold = parameters.rebuild_threshold;
+        let index = distance.with(|| unsafe {
+            T::load_index(
+                filename.as_ptr() as *mut _,
+                points_vec.as_ptr() as *mut T,
+                (points_vec.len() / N::to_usize()) as i32,
+                N::to_i32(),
+            )
+        });
+        if index.is_null() {
+            return Err(FlannError::FailedToLoadIndex);
+        }
+        Ok(Self {
+            index,
+            storage: vec![points_vec],
+            parameters: parameters.into(),
+            rebuild_threshold,
+            distance,
+            build_report: None,
             _phantom: PhantomData,
         })
     }
 
+    /// Saves the index to a file so that it can be restored with `load`.
+    ///
+    /// The points are not saved, so they must be kept separately.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FlannError> {
+        let filename = saved_index::path_to_cstring(path)?;
+        let retval = self
+            .distance
+            .with(|| unsafe { T::save_index(self.index, filename.as_ptr() as *mut _) });
+        if retval != 0 {
+            return Err(FlannError::FailedToSaveIndex);
+        }
+        Ok(())
+    }
+
     /// Adds a point to the index.
-    pub fn add(&mut self, point: GenericArray<T, N>) {
+    pub fn add(&mut self, point: GenericArray<T, N>) -> Result<(), FlannError> {
         let points_vec: Vec<T> = point.into_iter().collect();
-        let retval = unsafe {
+        let retval = self.distance.with(|| unsafe {
             T::add_points(
                 self.index,
                 points_vec.as_ptr() as *mut T,
@@ -67,21 +155,24 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
                 N::to_i32(),
                 self.rebuild_threshold,
             )
-        };
+        });
         self.storage.push(points_vec);
-        assert_eq!(retval, 0);
+        if retval != 0 {
+            return Err(FlannError::AddPointsFailed);
+        }
+        Ok(())
     }
 
     /// Adds multiple points to the index.
-    pub fn add_multiple<I>(&mut self, points: I)
+    pub fn add_multiple<I>(&mut self, points: I) -> Result<(), FlannError>
     where
         I: IntoIterator<Item = GenericArray<T, N>>,
     {
         let points_vec: Vec<T> = points.into_iter().flat_map(|p| p.into_iter()).collect();
         if points_vec.is_empty() {
-            return;
+            return Ok(());
         }
-        let retval = unsafe {
+        let retval = self.distance.with(|| unsafe {
             T::add_points(
                 self.index,
                 points_vec.as_ptr() as *mut T,
@@ -89,16 +180,26 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
                 N::to_i32(),
                 self.rebuild_threshold,
             )
-        };
+        });
         self.storage.push(points_vec);
-        assert_eq!(retval, 0);
+        if retval != 0 {
+            return Err(FlannError::AddPointsFailed);
+        }
+        Ok(())
     }
 
     /// Get the point that corresponds to this index `idx`.
+    ///
+    /// Returns `None` if there is no such point or FLANN has already
+    /// discarded it after it was removed.
     pub fn get(&self, idx: usize) -> Option<&GenericArray<T, N>> {
         if idx < self.len() {
-            let point = unsafe { T::get_point(self.index, idx as u32) };
-            assert!(!point.is_null());
+            let point = self
+                .distance
+                .with(|| unsafe { T::get_point(self.index, idx as u32) });
+            if point.is_null() {
+                return None;
+            }
             Some(unsafe { &*(point as *const GenericArray<T, N>) })
         } else {
             None
@@ -106,13 +207,35 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
     }
 
     /// Removes a point at index `idx`.
-    pub fn remove(&mut self, idx: usize) {
-        let retval = unsafe { T::remove_point(self.index, idx as u32) };
-        assert_eq!(retval, 0);
+    pub fn remove(&mut self, idx: usize) -> Result<(), FlannError> {
+        let retval = self
+            .distance
+            .with(|| unsafe { T::remove_point(self.index, idx as u32) });
+        if retval != 0 {
+            return Err(FlannError::RemoveFailed { idx });
+        }
+        Ok(())
+    }
+
+    /// Returns the parameters that the index uses.
+    pub fn parameters(&self) -> Parameters {
+        let mut parameters =
+            Parameters::from_raw(self.parameters).expect("Illegal FLANN parameters in index");
+        parameters.rebuild_threshold = self.rebuild_threshold;
+        parameters.distance_type = self.distance.distance_type;
+        parameters.distance_order = self.distance.order;
+        parameters
+    }
+
+    /// Returns what FLANN reported when building the index, or `None` if
+    /// the index was loaded.
+    pub fn build_report(&self) -> Option<&BuildReport> {
+        self.build_report.as_ref()
     }
 
     pub fn len(&self) -> usize {
-        unsafe { T::size(self.index) as usize }
+        self.distance
+            .with(|| unsafe { T::size(self.index) as usize })
     }
 
     pub fn is_empty(&self) -> bool {
@@ -120,89 +243,355 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
     }
 
     /// Performs a search to find only the closest neighbor.
-    pub fn find_nearest_neighbor(&mut self, point: &GenericArray<T, N>) -> Neighbor<T::ResultType> {
+    pub fn find_nearest_neighbor(
+        &self,
+        point: &GenericArray<T, N>,
+    ) -> Result<Neighbor<T::ResultType>, FlannError> {
         let mut index = -1;
-        let mut distance_squared = T::ResultType::default();
-        let retval = unsafe {
+        let mut distance = T::ResultType::default();
+        let mut flann_params = self.parameters;
+        let retval = self.distance.with(|| unsafe {
             T::find_nearest_neighbors_index(
                 self.index,
                 point.as_ptr() as *mut T,
                 1,
                 &mut index,
-                &mut distance_squared,
+                &mut distance,
                 1,
-                &mut self.parameters,
+                &mut flann_params,
             )
-        };
-        assert_eq!(retval, 0);
-        Neighbor {
-            index: index as usize,
-            distance_squared,
+        });
+        if retval != 0 {
+            return Err(FlannError::SearchFailed);
         }
+        Ok(Neighbor {
+            index: index as usize,
+            distance,
+        })
     }
 
     /// Performs k-NN search for `num` neighbors.
     /// If there are less points in the set than `num` it returns that many neighbors.
     pub fn find_nearest_neighbors(
-        &mut self,
+        &self,
         num: usize,
         point: &GenericArray<T, N>,
-    ) -> impl Iterator<Item = Neighbor<T::ResultType>> {
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_nearest_neighbors_raw(num, point, flann_params)
+    }
+
+    /// Performs k-NN search for `num` neighbors using `params` instead of
+    /// the search parameters the index was built with.
+    pub fn find_nearest_neighbors_with_params(
+        &self,
+        num: usize,
+        point: &GenericArray<T, N>,
+        params: &SearchParams,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_nearest_neighbors_raw(num, point, flann_params)
+    }
+
+    fn find_nearest_neighbors_raw(
+        &self,
+        num: usize,
+        point: &GenericArray<T, N>,
+        mut flann_params: raw::FLANNParameters,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
         let num = num.min(self.len());
         let mut indices: Vec<i32> = vec![-1; num];
-        let mut distances_squared: Vec<T::ResultType> = vec![T::ResultType::default(); num];
-        let retval = unsafe {
+        let mut distances: Vec<T::ResultType> = vec![T::ResultType::default(); num];
+        let retval = self.distance.with(|| unsafe {
             T::find_nearest_neighbors_index(
                 self.index,
                 point.as_ptr() as *mut T,
                 1,
                 indices.as_mut_ptr(),
-                distances_squared.as_mut_ptr(),
+                distances.as_mut_ptr(),
                 num as i32,
-                &mut self.parameters,
+                &mut flann_params,
             )
-        };
-        assert_eq!(retval, 0);
-        indices
+        });
+        if retval != 0 {
+            return Err(FlannError::SearchFailed);
+        }
+        Ok(indices
             .into_iter()
-            .zip(distances_squared.into_iter())
-            .map(|(index, distance_squared)| Neighbor {
+            .zip(distances.into_iter())
+            .map(|(index, distance)| Neighbor {
                 index: index as usize,
-                distance_squared,
-            })
+                distance,
+            }))
     }
 
     /// Performs k-NN search for `num` neighbors.
     /// If there are less points in the set than `num` it returns that many neighbors.
     pub fn find_nearest_neighbors_radius(
-        &mut self,
+        &self,
         num: usize,
-        radius_squared: f32,
+        radius: f32,
         point: &GenericArray<T, N>,
-    ) -> impl Iterator<Item = Neighbor<T::ResultType>> {
-        let num = num.min(self.len());
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_nearest_neighbors_radius_raw(num, radius, point, flann_params)
+    }
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance,
+    /// using `params` instead of the search parameters the index was built with.
+    pub fn find_nearest_neighbors_radius_with_params(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        params: &SearchParams,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_nearest_neighbors_radius_raw(num, radius, point, flann_params)
+    }
+
+    fn find_nearest_neighbors_radius_raw(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        flann_params: raw::FLANNParameters,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let neighbors = self.radius_search_raw(num.min(self.len()), radius, point, flann_params)?;
+        Ok(neighbors.into_iter())
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// and reports whether there were more.
+    pub fn find_within_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &GenericArray<T, N>,
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_within_radius_raw(num, radius, point, flann_params)
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// using `params` instead of the search parameters the index was built with.
+    pub fn find_within_radius_with_params(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        params: &SearchParams,
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_within_radius_raw(num, radius, point, flann_params)
+    }
+
+    fn find_within_radius_raw(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        flann_params: raw::FLANNParameters,
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        // One more neighbor than requested is searched for to tell if there are more.
+        let mut neighbors =
+            self.radius_search_raw(num.min(self.len()) + 1, radius, point, flann_params)?;
+        let truncated = neighbors.len() > num;
+        neighbors.truncate(num);
+        Ok(RadiusResult {
+            neighbors,
+            truncated,
+        })
+    }
+
+    /// Performs a search for all neighbors within `radius` distance.
+    ///
+    /// The neighbors are sorted by closest to furthest.
+    pub fn find_all_within_radius(
+        &self,
+        radius: f32,
+        point: &GenericArray<T, N>,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_all_within_radius_raw(radius, point, flann_params)
+    }
+
+    /// Performs a search for all neighbors within `radius` distance using
+    /// `params` instead of the search parameters the index was built with.
+    pub fn find_all_within_radius_with_params(
+        &self,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        params: &SearchParams,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_all_within_radius_raw(radius, point, flann_params)
+    }
+
+    fn find_all_within_radius_raw(
+        &self,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        flann_params: raw::FLANNParameters,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.radius_search_all_into(
+            radius,
+            point,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            flann_params,
+        )
+    }
+
+    /// Performs a search for all neighbors within the radius of several points.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    pub fn find_many_within_radius<'r, R: Into<Radii<'r>>>(
+        &self,
+        radii: R,
+        points: &[GenericArray<T, N>],
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError> {
+        let radii = radii.into();
+        radii.check(points.len())?;
+        let (mut indices, mut distances) = (Vec::new(), Vec::new());
+        points
+            .iter()
+            .enumerate()
+            .map(|(query, point)| {
+                self.radius_search_all_into(
+                    radii.get(query),
+                    point,
+                    &mut indices,
+                    &mut distances,
+                    self.parameters,
+                )
+            })
+            .collect()
+    }
+
+    /// Performs a search for all neighbors within the radius of several points
+    /// on the rayon thread pool.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    #[cfg(feature = "rayon")]
+    pub fn par_find_many_within_radius<'r, R: Into<Radii<'r>>>(
+        &self,
+        radii: R,
+        points: &[GenericArray<T, N>],
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError>
+    where
+        T: Sync,
+        T::ResultType: Send,
+    {
+        let radii = radii.into();
+        radii.check(points.len())?;
+        let neighbors = points
+            .par_iter()
+            .enumerate()
+            .map_init(
+                || (Vec::new(), Vec::new()),
+                |(indices, distances), (query, point)| {
+                    self.radius_search_all_into(
+                        radii.get(query),
+                        point,
+                        indices,
+                        distances,
+                        self.parameters,
+                    )
+                },
+            )
+            .collect::<Result<Vec<_>, FlannError>>()?;
+        Ok(neighbors.into_iter().collect())
+    }
+
+    /// Searches for all neighbors within `radius` distance, reusing `indices`
+    /// and `distances` between searches.
+    ///
+    /// The buffers start small and double whenever a search fills them, so
+    /// dense queries are searched again but sparse ones stay cheap.
+    fn radius_search_all_into(
+        &self,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        indices: &mut Vec<i32>,
+        distances: &mut Vec<T::ResultType>,
+        flann_params: raw::FLANNParameters,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        // No more neighbors than there are points can be found.
+        let max_len = self.len();
+        if max_len == 0 {
+            return Ok(Vec::new());
+        }
+        let mut len = indices.len().max(MIN_RADIUS_BUFFER_LEN).min(max_len);
+        loop {
+            indices.resize(len, -1);
+            distances.resize(len, T::ResultType::default());
+            let found =
+                self.radius_search_into_raw(radius, point, indices, distances, flann_params)?;
+            if found < len || len == max_len {
+                return Ok(indices[..found]
+                    .iter()
+                    .zip(&distances[..found])
+                    .map(|(&index, distance)| Neighbor {
+                        index: index as usize,
+                        distance: distance.clone(),
+                    })
+                    .collect());
+            }
+            len = len.saturating_mul(2).min(max_len);
+        }
+    }
+
+    /// Searches for at most `num` neighbors within `radius` distance.
+    fn radius_search_raw(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        flann_params: raw::FLANNParameters,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
         let mut indices: Vec<i32> = vec![-1; num];
-        let mut distances_squared: Vec<T::ResultType> = vec![T::ResultType::default(); num];
-        let retval = unsafe {
+        let mut distances: Vec<T::ResultType> = vec![T::ResultType::default(); num];
+        let found =
+            self.radius_search_into_raw(radius, point, &mut indices, &mut distances, flann_params)?;
+        Ok(indices
+            .into_iter()
+            .zip(distances.into_iter())
+            .take(found)
+            .map(|(index, distance)| Neighbor {
+                index: index as usize,
+                distance,
+            })
+            .collect())
+    }
+
+    /// Searches for as many neighbors within `radius` distance as fit into
+    /// `indices` and `distances` and returns how many were found.
+    ///
+    /// If the buffers are empty, FLANN counts all neighbors within `radius`.
+    fn radius_search_into_raw(
+        &self,
+        radius: f32,
+        point: &GenericArray<T, N>,
+        indices: &mut [i32],
+        distances: &mut [T::ResultType],
+        mut flann_params: raw::FLANNParameters,
+    ) -> Result<usize, FlannError> {
+        let retval = self.distance.with(|| unsafe {
             T::radius_search(
                 self.index,
                 point.as_ptr() as *mut T,
                 indices.as_mut_ptr(),
-                distances_squared.as_mut_ptr(),
-                num as i32,
-                radius_squared,
-                &mut self.parameters,
+                distances.as_mut_ptr(),
+                indices.len().min(distances.len()) as i32,
+                radius,
+                &mut flann_params,
             )
-        };
-        assert!(retval >= 0);
-        indices
-            .into_iter()
-            .zip(distances_squared.into_iter())
-            .take(retval as usize)
-            .map(|(index, distance_squared)| Neighbor {
-                index: index as usize,
-                distance_squared,
-            })
+        });
+        if retval < 0 {
+            return Err(FlannError::SearchFailed);
+        }
+        Ok(retval as usize)
     }
 
     /// Performs k-NN search on `num` neighbors for several points.
@@ -210,43 +599,77 @@ impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
     /// If there are less points in the set than `num` it returns that many
     /// neighbors for each point.
     pub fn find_many_nearest_neighbors(
-        &mut self,
+        &self,
         num: usize,
         points: &[GenericArray<T, N>],
-    ) -> IntoChunks<impl Iterator<Item = Neighbor<T::ResultType>>> {
-        let neighbor_from_index_distance = |(index, distance_squared)| Neighbor {
-            index: index as usize,
-            distance_squared,
-        };
-        if points.is_empty() {
-            let indices: Vec<i32> = Vec::new();
-            let distances: Vec<T::ResultType> = Vec::new();
-            return indices
-                .into_iter()
-                .zip(distances.into_iter())
-                .map(neighbor_from_index_distance)
-                .chunks(num);
-        }
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_many_nearest_neighbors_raw(num, points, flann_params)
+    }
+
+    /// Performs k-NN search on `num` neighbors for several points using `params`
+    /// instead of the search parameters the index was built with.
+    pub fn find_many_nearest_neighbors_with_params(
+        &self,
+        num: usize,
+        points: &[GenericArray<T, N>],
+        params: &SearchParams,
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_many_nearest_neighbors_raw(num, points, flann_params)
+    }
+
+    /// Performs k-NN search for `num` neighbors for several points on the rayon thread pool.
+    ///
+    /// The points are split into chunks that are searched in parallel with `cores` set to 1,
+    /// so FLANN doesn't start threads of its own. The neighbors are returned in the same
+    /// order as the points.
+    #[cfg(feature = "rayon")]
+    pub fn par_find_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[GenericArray<T, N>],
+    ) -> Result<KnnResults<T::ResultType>, FlannError>
+    where
+        T: Sync,
+        T::ResultType: Send,
+    {
+        let mut flann_params = self.parameters;
+        flann_params.cores = 1;
+        let chunks = points
+            .par_chunks(PARALLEL_CHUNK_LEN)
+            .map(|chunk| self.find_many_nearest_neighbors_raw(num, chunk, flann_params))
+            .collect::<Result<Vec<_>, FlannError>>()?;
+        Ok(KnnResults::concat(num.min(self.len()), chunks))
+    }
+
+    fn find_many_nearest_neighbors_raw(
+        &self,
+        num: usize,
+        points: &[GenericArray<T, N>],
+        mut flann_params: raw::FLANNParameters,
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
         let num = num.min(self.len());
+        if num == 0 || points.is_empty() {
+            return Ok(KnnResults::new(num, points.len(), Vec::new()));
+        }
         let mut indices: Vec<i32> = vec![-1; num * points.len()];
-        let mut distances_squared: Vec<T::ResultType> =
-            vec![T::ResultType::default(); num * points.len()];
-        let retval = unsafe {
+        let mut distances: Vec<T::ResultType> = vec![T::ResultType::default(); num * points.len()];
+        let retval = self.distance.with(|| unsafe {
             T::find_nearest_neighbors_index(
                 self.index,
                 points.as_ptr() as *mut T,
                 points.len() as i32,
                 indices.as_mut_ptr(),
-                distances_squared.as_mut_ptr(),
+                distances.as_mut_ptr(),
                 num as i32,
-                &mut self.parameters,
+                &mut flann_params,
             )
-        };
-        assert_eq!(retval, 0);
-        indices
-            .into_iter()
-            .zip(distances_squared.into_iter())
-            .map(neighbor_from_index_distance)
-            .chunks(num)
+        });
+        if retval != 0 {
+            return Err(FlannError::SearchFailed);
+        }
+        let indices = indices.into_iter().map(|index| index as usize).collect();
+        Ok(KnnResults::from_flat(num, points.len(), indices, distances))
     }
 }
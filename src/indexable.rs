@@ -1,10 +1,13 @@
-use raw::{flann_index_t, FLANNParameters};
+use raw::{flann_datatype_t, flann_index_t, FLANNParameters};
 use std::fmt::Debug;
-use std::os::raw::{c_int, c_uint};
+use std::os::raw::{c_char, c_int, c_uint};
 
 pub unsafe trait Indexable: Clone + Debug + Default {
     type ResultType: Clone + Debug + Default;
 
+    /// The FLANN data type tag of `Self`.
+    const DATA_TYPE: flann_datatype_t;
+
     unsafe fn build_index(
         dataset: *mut Self,
         rows: c_int,
@@ -31,6 +34,15 @@ pub unsafe trait Indexable: Clone + Debug + Default {
 
     unsafe fn used_memory(index_ptr: flann_index_t) -> c_int;
 
+    unsafe fn save_index(index_id: flann_index_t, filename: *mut c_char) -> c_int;
+
+    unsafe fn load_index(
+        filename: *mut c_char,
+        dataset: *mut Self,
+        rows: c_int,
+        cols: c_int,
+    ) -> flann_index_t;
+
     unsafe fn find_nearest_neighbors_index(
         index_id: flann_index_t,
         testset: *mut Self,
@@ -52,4 +64,13 @@ pub unsafe trait Indexable: Clone + Debug + Default {
     ) -> c_int;
 
     unsafe fn free_index(index_id: flann_index_t, flann_params: *mut FLANNParameters) -> c_int;
+
+    unsafe fn compute_cluster_centers(
+        dataset: *mut Self,
+        rows: c_int,
+        cols: c_int,
+        clusters: c_int,
+        result: *mut Self::ResultType,
+        flann_params: *mut FLANNParameters,
+    ) -> c_int;
 }
@@ -1,11 +1,12 @@
-use raw::{self, flann_index_t, FLANNParameters};
-use std::os::raw::{c_int, c_uint};
+use raw::{self, flann_datatype_t, flann_index_t, FLANNParameters};
+use std::os::raw::{c_char, c_int, c_uint};
 use Indexable;
 
 macro_rules! impl_index {
     (
         $t: ty,
         $r: ty,
+        $data_type: ident,
         $build_index: ident,
         $add_points: ident,
         $remove_point: ident,
@@ -13,13 +14,18 @@ macro_rules! impl_index {
         $veclen: ident,
         $size: ident,
         $used_memory: ident,
+        $save_index: ident,
+        $load_index: ident,
         $find_nearest_neighbors_index: ident,
         $radius_search: ident,
         $free_index: ident,
+        $compute_cluster_centers: ident,
     ) => {
         unsafe impl Indexable for $t {
             type ResultType = $r;
 
+            const DATA_TYPE: flann_datatype_t = raw::$data_type;
+
             #[inline]
             unsafe fn build_index(
                 dataset: *mut Self,
@@ -67,6 +73,21 @@ macro_rules! impl_index {
                 raw::$used_memory(index_ptr)
             }
 
+            #[inline]
+            unsafe fn save_index(index_id: flann_index_t, filename: *mut c_char) -> c_int {
+                raw::$save_index(index_id, filename)
+            }
+
+            #[inline]
+            unsafe fn load_index(
+                filename: *mut c_char,
+                dataset: *mut Self,
+                rows: c_int,
+                cols: c_int,
+            ) -> flann_index_t {
+                raw::$load_index(filename, dataset, rows, cols)
+            }
+
             #[inline]
             unsafe fn find_nearest_neighbors_index(
                 index_id: flann_index_t,
@@ -116,6 +137,18 @@ macro_rules! impl_index {
             ) -> c_int {
                 raw::$free_index(index_id, flann_params)
             }
+
+            #[inline]
+            unsafe fn compute_cluster_centers(
+                dataset: *mut Self,
+                rows: c_int,
+                cols: c_int,
+                clusters: c_int,
+                result: *mut Self::ResultType,
+                flann_params: *mut FLANNParameters,
+            ) -> c_int {
+                raw::$compute_cluster_centers(dataset, rows, cols, clusters, result, flann_params)
+            }
         }
     };
 }
@@ -123,6 +156,7 @@ macro_rules! impl_index {
 impl_index!(
     f32,
     f32,
+    flann_datatype_t_FLANN_FLOAT32,
     flann_build_index_float,
     flann_add_points_float,
     flann_remove_point_float,
@@ -130,14 +164,18 @@ impl_index!(
     flann_veclen_float,
     flann_size_float,
     flann_used_memory_float,
+    flann_save_index_float,
+    flann_load_index_float,
     flann_find_nearest_neighbors_index_float,
     flann_radius_search_float,
     flann_free_index_float,
+    flann_compute_cluster_centers_float,
 );
 
 impl_index!(
     f64,
     f64,
+    flann_datatype_t_FLANN_FLOAT64,
     flann_build_index_double,
     flann_add_points_double,
     flann_remove_point_double,
@@ -145,14 +183,18 @@ impl_index!(
     flann_veclen_double,
     flann_size_double,
     flann_used_memory_double,
+    flann_save_index_double,
+    flann_load_index_double,
     flann_find_nearest_neighbors_index_double,
     flann_radius_search_double,
     flann_free_index_double,
+    flann_compute_cluster_centers_double,
 );
 
 impl_index!(
     u8,
     f32,
+    flann_datatype_t_FLANN_UINT8,
     flann_build_index_byte,
     flann_add_points_byte,
     flann_remove_point_byte,
@@ -160,14 +202,18 @@ impl_index!(
     flann_veclen_byte,
     flann_size_byte,
     flann_used_memory_byte,
+    flann_save_index_byte,
+    flann_load_index_byte,
     flann_find_nearest_neighbors_index_byte,
     flann_radius_search_byte,
     flann_free_index_byte,
+    flann_compute_cluster_centers_byte,
 );
 
 impl_index!(
     i32,
     f32,
+    flann_datatype_t_FLANN_INT32,
     flann_build_index_int,
     flann_add_points_int,
     flann_remove_point_int,
@@ -175,7 +221,10 @@ impl_index!(
     flann_veclen_int,
     flann_size_int,
     flann_used_memory_int,
+    flann_save_index_int,
+    flann_load_index_int,
     flann_find_nearest_neighbors_index_int,
     flann_radius_search_int,
     flann_free_index_int,
+    flann_compute_cluster_centers_int,
 );
@@ -0,0 +1,47 @@
+//! Reading and writing datasets in the file formats used by ANN benchmarks.
+//!
+//! Datasets are read into the flat layout that `SliceIndex::new` expects:
+//! all points in one `Vec` in component order, with `point_len` components
+//! per point.
+
+mod npy;
+mod vecs;
+
+pub use self::npy::{
+    load_npy, load_npz, read_npy, save_neighbors_npy, save_neighbors_npz, save_npy, write_npy,
+    NpyElement, NpzReader, NpzWriter,
+};
+
+#[cfg(feature = "memmap2")]
+pub use self::vecs::MappedVecs;
+pub use self::vecs::{
+    load_bvecs, load_fvecs, load_ivecs, load_vecs, save_bvecs, save_fvecs, save_ivecs, save_vecs,
+    write_vecs, VecsElement, VecsReader,
+};
+
+/// Points read from a file, in component order.
+#[derive(Debug, Clone)]
+pub struct Dataset<T> {
+    pub point_len: usize,
+    pub points: Vec<T>,
+}
+
+impl<T> Dataset<T> {
+    /// The number of points.
+    pub fn len(&self) -> usize {
+        self.points.len().checked_div(self.point_len).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Get the point that corresponds to this index `idx`.
+    pub fn get(&self, idx: usize) -> Option<&[T]> {
+        if idx < self.len() {
+            Some(&self.points[idx * self.point_len..(idx + 1) * self.point_len])
+        } else {
+            None
+        }
+    }
+}
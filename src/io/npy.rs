@@ -0,0 +1,565 @@
+//! The NumPy `.npy` format and uncompressed `.npz` archives of `.npy` files.
+//!
+//! Only 2-D arrays in C order are supported. The first dimension is the
+//! number of points and the second is `point_len`.
+
+use super::Dataset;
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use FlannError;
+use Neighbor;
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+/// NumPy aligns the data of `.npy` files to this many bytes.
+const ALIGNMENT: usize = 64;
+/// How many bytes are converted at once when reading arrays.
+const READ_CHUNK_LEN: usize = 1 << 16;
+
+/// An element type of `.npy` arrays.
+pub trait NpyElement: Copy + Default {
+    /// The NumPy type code without byte order, e.g. `f4`.
+    const TYPE_CODE: &'static str;
+    /// The size of an element in bytes.
+    const SIZE: usize;
+
+    fn from_bytes(bytes: &[u8], little_endian: bool) -> Self;
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_npy_element {
+    ($t: ty, $type_code: expr) => {
+        impl NpyElement for $t {
+            const TYPE_CODE: &'static str = $type_code;
+            const SIZE: usize = std::mem::size_of::<$t>();
+
+            fn from_bytes(bytes: &[u8], little_endian: bool) -> Self {
+                let mut array = [0u8; std::mem::size_of::<$t>()];
+                array.copy_from_slice(bytes);
+                if little_endian {
+                    <$t>::from_le_bytes(array)
+                } else {
+                    <$t>::from_be_bytes(array)
+                }
+            }
+
+            fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    };
+}
+
+impl_npy_element!(f32, "f4");
+impl_npy_element!(f64, "f8");
+impl_npy_element!(u8, "u1");
+impl_npy_element!(i32, "i4");
+impl_npy_element!(i64, "i8");
+
+/// Reads a 2-D array in C order from a `.npy` stream.
+pub fn read_npy<T: NpyElement, R: Read>(reader: &mut R) -> Result<Dataset<T>, FlannError> {
+    let mut preamble = [0u8; 8];
+    read_exact(reader, &mut preamble)?;
+    if &preamble[..6] != MAGIC {
+        return Err(FlannError::InvalidDataset);
+    }
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            read_exact(reader, &mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            read_exact(reader, &mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        _ => return Err(FlannError::InvalidDataset),
+    };
+    let mut header = vec![0u8; header_len];
+    read_exact(reader, &mut header)?;
+    let header = String::from_utf8(header).map_err(|_| FlannError::InvalidDataset)?;
+    let header = Header::parse(&header.replace('"', "'"))?;
+
+    let (byte_order, type_code) = header.descr.split_at(1);
+    let little_endian = match byte_order {
+        "<" | "|" => true,
+        ">" => false,
+        "=" => cfg!(target_endian = "little"),
+        _ => return Err(FlannError::InvalidDataset),
+    };
+    if type_code != T::TYPE_CODE {
+        return Err(FlannError::DatasetTypeMismatch);
+    }
+    if header.fortran_order {
+        return Err(FlannError::InvalidDataset);
+    }
+    let (rows, cols) = match header.shape[..] {
+        [rows, cols] => (rows, cols),
+        _ => return Err(FlannError::InvalidDataset),
+    };
+
+    let len = rows.checked_mul(cols).ok_or(FlannError::InvalidDataset)?;
+    let mut points = Vec::new();
+    let mut buf = vec![0u8; READ_CHUNK_LEN - READ_CHUNK_LEN % T::SIZE];
+    let mut remaining = len.checked_mul(T::SIZE).ok_or(FlannError::InvalidDataset)?;
+    while remaining > 0 {
+        let chunk = &mut buf[..remaining.min(READ_CHUNK_LEN - READ_CHUNK_LEN % T::SIZE)];
+        read_exact(reader, chunk)?;
+        points.extend(
+            chunk
+                .chunks(T::SIZE)
+                .map(|bytes| T::from_bytes(bytes, little_endian)),
+        );
+        remaining -= chunk.len();
+    }
+    Ok(Dataset {
+        point_len: cols,
+        points,
+    })
+}
+
+/// Writes points that are in a slice of memory in component order where
+/// there are `point_len` components as a `(points.len() / point_len, point_len)`
+/// array to a `.npy` stream.
+pub fn write_npy<T: NpyElement, W: Write>(
+    writer: &mut W,
+    point_len: usize,
+    points: &[T],
+) -> Result<(), FlannError> {
+    if point_len == 0 || points.len() % point_len != 0 {
+        return Err(FlannError::InvalidFlatPointsLen {
+            expected: point_len,
+            got: points.len(),
+        });
+    }
+    let mut header = format!(
+        "{{'descr': '{}{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        if T::SIZE == 1 { '|' } else { '<' },
+        T::TYPE_CODE,
+        points.len() / point_len,
+        point_len
+    );
+    // The magic, version and header length take 10 bytes and the header ends with a newline.
+    let padding = (ALIGNMENT - (10 + header.len() + 1) % ALIGNMENT) % ALIGNMENT;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + points.len() * T::SIZE);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for &point in points {
+        point.extend_le_bytes(&mut bytes);
+    }
+    writer
+        .write_all(&bytes)
+        .and_then(|_| writer.flush())
+        .map_err(|_| FlannError::FailedToWriteDataset)
+}
+
+/// Reads a 2-D array in C order from a `.npy` file.
+pub fn load_npy<T: NpyElement, P: AsRef<Path>>(path: P) -> Result<Dataset<T>, FlannError> {
+    let file = File::open(path).map_err(|_| FlannError::FailedToReadDataset)?;
+    read_npy(&mut BufReader::new(file))
+}
+
+/// Writes points to a `.npy` file.
+///
+/// See `write_npy` for the layout of `points`.
+pub fn save_npy<T: NpyElement, P: AsRef<Path>>(
+    path: P,
+    point_len: usize,
+    points: &[T],
+) -> Result<(), FlannError> {
+    let file = File::create(path).map_err(|_| FlannError::FailedToWriteDataset)?;
+    write_npy(&mut BufWriter::new(file), point_len, points)
+}
+
+/// Reads the array `name` from an uncompressed `.npz` file.
+///
+/// `name` is the name the array was saved under, without the `.npy` extension.
+pub fn load_npz<T: NpyElement, P: AsRef<Path>>(
+    path: P,
+    name: &str,
+) -> Result<Dataset<T>, FlannError> {
+    let file = File::open(path).map_err(|_| FlannError::FailedToReadDataset)?;
+    NpzReader::new(BufReader::new(file))?.read(name)
+}
+
+/// Writes `(n_queries, k)` arrays of the neighbor indices and distances of
+/// search results to two `.npy` files.
+///
+/// Every query must have the same number of neighbors.
+pub fn save_neighbors_npy<D, I, N, P, Q>(
+    indices_path: P,
+    distances_path: Q,
+    neighbors: I,
+) -> Result<(), FlannError>
+where
+    D: NpyElement,
+    I: IntoIterator<Item = N>,
+    N: IntoIterator,
+    N::Item: Borrow<Neighbor<D>>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (k, indices, distances) = neighbor_arrays(neighbors)?;
+    save_npy(indices_path, k, &indices)?;
+    save_npy(distances_path, k, &distances)
+}
+
+/// Writes `(n_queries, k)` arrays of the neighbor indices and distances of
+/// search results to an uncompressed `.npz` file as `indices` and `distances`.
+///
+/// Every query must have the same number of neighbors.
+pub fn save_neighbors_npz<D, I, N, P>(path: P, neighbors: I) -> Result<(), FlannError>
+where
+    D: NpyElement,
+    I: IntoIterator<Item = N>,
+    N: IntoIterator,
+    N::Item: Borrow<Neighbor<D>>,
+    P: AsRef<Path>,
+{
+    let (k, indices, distances) = neighbor_arrays(neighbors)?;
+    let file = File::create(path).map_err(|_| FlannError::FailedToWriteDataset)?;
+    let mut npz = NpzWriter::new(BufWriter::new(file));
+    npz.add("indices", k, &indices)?;
+    npz.add("distances", k, &distances)?;
+    npz.finish()?;
+    Ok(())
+}
+
+/// Collects search results into flat index and distance arrays with `k` columns.
+fn neighbor_arrays<D, I, N>(neighbors: I) -> Result<(usize, Vec<i64>, Vec<D>), FlannError>
+where
+    D: Copy,
+    I: IntoIterator<Item = N>,
+    N: IntoIterator,
+    N::Item: Borrow<Neighbor<D>>,
+{
+    let mut k = None;
+    let mut indices = Vec::new();
+    let mut distances = Vec::new();
+    for query in neighbors {
+        let start = indices.len();
+        for neighbor in query {
+            let neighbor = neighbor.borrow();
+            indices.push(neighbor.index as i64);
+            distances.push(neighbor.distance);
+        }
+        let got = indices.len() - start;
+        match k {
+            Some(expected) if expected != got => {
+                return Err(FlannError::InvalidPointDimensionality { expected, got });
+            }
+            _ => k = Some(got),
+        }
+    }
+    match k {
+        Some(k) if k > 0 => Ok((k, indices, distances)),
+        _ => Err(FlannError::ZeroInputPoints),
+    }
+}
+
+/// The fields of a `.npy` header that are needed to read the array.
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+impl Header {
+    /// Parses the Python dict literal of a `.npy` header.
+    fn parse(header: &str) -> Result<Self, FlannError> {
+        let value = |key: &str| {
+            header
+                .find(&format!("'{}':", key))
+                .map(|start| header[start + key.len() + 3..].trim_start())
+                .ok_or(FlannError::InvalidDataset)
+        };
+        let descr = value("descr")?;
+        let descr = descr
+            .strip_prefix('\'')
+            .and_then(|descr| descr.split('\'').next())
+            .filter(|descr| descr.len() > 1)
+            .ok_or(FlannError::InvalidDataset)?;
+        let fortran_order = value("fortran_order")?.starts_with("True");
+        let shape = value("shape")?
+            .strip_prefix('(')
+            .and_then(|shape| shape.split(')').next())
+            .ok_or(FlannError::InvalidDataset)?
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.parse().map_err(|_| FlannError::InvalidDataset))
+            .collect::<Result<Vec<usize>, FlannError>>()?;
+        Ok(Header {
+            descr: descr.to_owned(),
+            fortran_order,
+            shape,
+        })
+    }
+}
+
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), FlannError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => FlannError::InvalidDataset,
+        _ => FlannError::FailedToReadDataset,
+    })
+}
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const END_OF_CENTRAL_DIRECTORY_LEN: u64 = 22;
+/// 1980-01-01, the earliest date a zip file can store.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// An entry in the central directory of a zip file.
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Reads arrays from an uncompressed `.npz` file, as written by `numpy.savez`.
+pub struct NpzReader<R> {
+    reader: R,
+    entries: Vec<ZipEntry>,
+}
+
+impl<R: Read + Seek> NpzReader<R> {
+    /// Reads the list of arrays in the archive.
+    pub fn new(mut reader: R) -> Result<Self, FlannError> {
+        let end = find_end_of_central_directory(&mut reader)?;
+        let num_entries = u16::from_le_bytes([end[10], end[11]]);
+        let directory_offset = u32::from_le_bytes([end[16], end[17], end[18], end[19]]);
+        reader
+            .seek(SeekFrom::Start(directory_offset.into()))
+            .map_err(|_| FlannError::FailedToReadDataset)?;
+        let mut entries = Vec::with_capacity(num_entries.into());
+        for _ in 0..num_entries {
+            let mut header = [0u8; 46];
+            read_exact(&mut reader, &mut header)?;
+            let field16 = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+            let field32 = |at: usize| {
+                u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+            };
+            if field32(0) != CENTRAL_HEADER_SIGNATURE {
+                return Err(FlannError::InvalidDataset);
+            }
+            // Compressed entries and zip64 entries are not supported.
+            let size = field32(24);
+            if field16(10) != 0 || field32(20) != size || size == u32::MAX {
+                return Err(FlannError::InvalidDataset);
+            }
+            let mut name = vec![0u8; field16(28).into()];
+            read_exact(&mut reader, &mut name)?;
+            let skip = i64::from(field16(30)) + i64::from(field16(32));
+            reader
+                .seek(SeekFrom::Current(skip))
+                .map_err(|_| FlannError::FailedToReadDataset)?;
+            entries.push(ZipEntry {
+                name: String::from_utf8(name).map_err(|_| FlannError::InvalidDataset)?,
+                crc: field32(16),
+                size,
+                offset: field32(42),
+            });
+        }
+        Ok(Self { reader, entries })
+    }
+
+    /// The names of the arrays in the archive, without the `.npy` extension.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.trim_end_matches(".npy"))
+    }
+
+    /// Reads the array `name`, given without the `.npy` extension.
+    pub fn read<T: NpyElement>(&mut self, name: &str) -> Result<Dataset<T>, FlannError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name.trim_end_matches(".npy") == name)
+            .ok_or(FlannError::FailedToReadDataset)?;
+        self.reader
+            .seek(SeekFrom::Start(entry.offset.into()))
+            .map_err(|_| FlannError::FailedToReadDataset)?;
+        let mut header = [0u8; 30];
+        read_exact(&mut self.reader, &mut header)?;
+        if u32::from_le_bytes([header[0], header[1], header[2], header[3]])
+            != LOCAL_HEADER_SIGNATURE
+        {
+            return Err(FlannError::InvalidDataset);
+        }
+        let skip = i64::from(u16::from_le_bytes([header[26], header[27]]))
+            + i64::from(u16::from_le_bytes([header[28], header[29]]));
+        self.reader
+            .seek(SeekFrom::Current(skip))
+            .map_err(|_| FlannError::FailedToReadDataset)?;
+        let mut data = (&mut self.reader).take(entry.size.into());
+        read_npy(&mut data)
+    }
+}
+
+/// Searches the end of the file for the end of central directory record.
+fn find_end_of_central_directory<R: Read + Seek>(reader: &mut R) -> Result<Vec<u8>, FlannError> {
+    let file_len = reader
+        .seek(SeekFrom::End(0))
+        .map_err(|_| FlannError::FailedToReadDataset)?;
+    // The record may be followed by a comment of up to 65535 bytes.
+    let tail_len = file_len.min(END_OF_CENTRAL_DIRECTORY_LEN + 0xffff);
+    reader
+        .seek(SeekFrom::Start(file_len - tail_len))
+        .map_err(|_| FlannError::FailedToReadDataset)?;
+    let mut tail = vec![0u8; tail_len as usize];
+    read_exact(reader, &mut tail)?;
+    let signature = END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes();
+    let start = (0..tail
+        .len()
+        .saturating_sub(END_OF_CENTRAL_DIRECTORY_LEN as usize - 1))
+        .rev()
+        .find(|&start| tail[start..start + 4] == signature)
+        .ok_or(FlannError::InvalidDataset)?;
+    Ok(tail[start..start + END_OF_CENTRAL_DIRECTORY_LEN as usize].to_vec())
+}
+
+/// Writes arrays to an uncompressed `.npz` file that `numpy.load` can read.
+pub struct NpzWriter<W> {
+    writer: W,
+    entries: Vec<ZipEntry>,
+    offset: u64,
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            entries: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Adds an array named `name`, which must not have the `.npy` extension.
+    ///
+    /// See `write_npy` for the layout of `points`.
+    pub fn add<T: NpyElement>(
+        &mut self,
+        name: &str,
+        point_len: usize,
+        points: &[T],
+    ) -> Result<(), FlannError> {
+        let mut data = Vec::new();
+        write_npy(&mut data, point_len, points)?;
+        let name = format!("{}.npy", name);
+        if data.len() >= u32::MAX as usize
+            || self.offset >= u64::from(u32::MAX)
+            || name.len() > u16::MAX as usize
+        {
+            return Err(FlannError::FailedToWriteDataset);
+        }
+        let entry = ZipEntry {
+            crc: crc32(&data),
+            size: data.len() as u32,
+            offset: self.offset as u32,
+            name,
+        };
+        let mut header = Vec::with_capacity(30 + entry.name.len());
+        header.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        extend_entry_fields(&mut header, &entry);
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(entry.name.as_bytes());
+        self.write(&header)?;
+        self.write(&data)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory and returns the writer.
+    pub fn finish(mut self) -> Result<W, FlannError> {
+        let directory_offset = self.offset;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            // The version made by.
+            directory.extend_from_slice(&20u16.to_le_bytes());
+            extend_entry_fields(&mut directory, entry);
+            // The extra field, comment, disk number and attribute fields are all empty.
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        if self.entries.len() > u16::MAX as usize
+            || directory_offset + directory.len() as u64 >= u64::from(u32::MAX)
+        {
+            return Err(FlannError::FailedToWriteDataset);
+        }
+        directory.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        directory.extend_from_slice(&[0; 4]);
+        directory.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        directory.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        directory.extend_from_slice(&((directory.len() - 12) as u32).to_le_bytes());
+        directory.extend_from_slice(&(directory_offset as u32).to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        self.write(&directory)?;
+        self.writer
+            .flush()
+            .map_err(|_| FlannError::FailedToWriteDataset)?;
+        Ok(self.writer)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), FlannError> {
+        self.writer
+            .write_all(bytes)
+            .map_err(|_| FlannError::FailedToWriteDataset)?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Appends the fields that local and central headers share, from the version
+/// needed to extract up to the name length.
+fn extend_entry_fields(bytes: &mut Vec<u8>, entry: &ZipEntry) {
+    // The version needed, flags, compression method (stored) and modification time.
+    bytes.extend_from_slice(&20u16.to_le_bytes());
+    bytes.extend_from_slice(&[0; 6]);
+    bytes.extend_from_slice(&DOS_DATE.to_le_bytes());
+    bytes.extend_from_slice(&entry.crc.to_le_bytes());
+    bytes.extend_from_slice(&entry.size.to_le_bytes());
+    bytes.extend_from_slice(&entry.size.to_le_bytes());
+    bytes.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
@@ -0,0 +1,386 @@
+//! The `.fvecs`, `.ivecs` and `.bvecs` formats.
+//!
+//! Every record is the number of components as a little-endian `i32`
+//! followed by the components, which are little-endian `f32`, little-endian
+//! `i32` or bytes respectively.
+
+use super::Dataset;
+#[cfg(feature = "memmap2")]
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::marker::PhantomData;
+#[cfg(feature = "memmap2")]
+use std::ops::Range;
+use std::path::Path;
+use FlannError;
+
+/// A component type of the `.fvecs`, `.ivecs` and `.bvecs` formats.
+pub trait VecsElement: Copy + Default {
+    /// The size of a component in bytes.
+    const SIZE: usize;
+
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>);
+}
+
+impl VecsElement for f32 {
+    const SIZE: usize = 4;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl VecsElement for i32 {
+    const SIZE: usize = 4;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl VecsElement for u8 {
+    const SIZE: usize = 1;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.push(self);
+    }
+}
+
+/// Streams records from a reader, checking that they all have the same
+/// number of components.
+pub struct VecsReader<T, R> {
+    reader: R,
+    point_len: Option<usize>,
+    /// The number of bytes that are left in the reader, if it is known.
+    remaining: Option<u64>,
+    buf: Vec<u8>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: VecsElement, R: Read> VecsReader<T, R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            point_len: None,
+            remaining: None,
+            buf: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Makes a reader that rejects records which are longer than the
+    /// `max_len` bytes that are left in `reader`, such as the length of a file.
+    pub fn with_max_len(reader: R, max_len: u64) -> Self {
+        Self {
+            remaining: Some(max_len),
+            ..Self::new(reader)
+        }
+    }
+
+    /// The number of components of every point, known once a record was read.
+    pub fn point_len(&self) -> Option<usize> {
+        self.point_len
+    }
+
+    /// Reads the next record and appends its components to `points`.
+    ///
+    /// Returns `false` if there are no more records.
+    pub fn read_into(&mut self, points: &mut Vec<T>) -> Result<bool, FlannError> {
+        let mut header = [0u8; 4];
+        if !read_or_eof(&mut self.reader, &mut header)? {
+            return Ok(false);
+        }
+        let len = i32::from_le_bytes(header);
+        if len <= 0 {
+            return Err(FlannError::InvalidDataset);
+        }
+        let len = len as usize;
+        match self.point_len {
+            Some(point_len) if point_len != len => {
+                return Err(FlannError::InvalidPointDimensionality {
+                    expected: point_len,
+                    got: len,
+                });
+            }
+            _ => self.point_len = Some(len),
+        }
+        // `len` fits into an `i32`, so this can't overflow.
+        let record_len = 4 + len as u64 * T::SIZE as u64;
+        if let Some(remaining) = self.remaining {
+            if record_len > remaining {
+                return Err(FlannError::InvalidDataset);
+            }
+            self.remaining = Some(remaining - record_len);
+        }
+        // Reading through `take` only grows the buffer as data arrives, so a
+        // corrupted header can't make it allocate more than the reader holds.
+        self.buf.clear();
+        let read = (&mut self.reader)
+            .take(record_len - 4)
+            .read_to_end(&mut self.buf)
+            .map_err(|_| FlannError::FailedToReadDataset)?;
+        if read as u64 != record_len - 4 {
+            return Err(FlannError::InvalidDataset);
+        }
+        points.extend(self.buf.chunks(T::SIZE).map(T::from_le_slice));
+        Ok(true)
+    }
+
+    /// Reads up to `max_points` records into the flat layout.
+    ///
+    /// Returns an empty `Vec` once there are no more records.
+    pub fn read_chunk(&mut self, max_points: usize) -> Result<Vec<T>, FlannError> {
+        let mut points = Vec::new();
+        for _ in 0..max_points {
+            if !self.read_into(&mut points)? {
+                break;
+            }
+        }
+        Ok(points)
+    }
+
+    /// Reads all remaining records.
+    pub fn read_all(mut self) -> Result<Dataset<T>, FlannError> {
+        let mut points = Vec::new();
+        while self.read_into(&mut points)? {}
+        Ok(Dataset {
+            point_len: self.point_len.unwrap_or(0),
+            points,
+        })
+    }
+}
+
+impl<T: VecsElement, R: Read> Iterator for VecsReader<T, R> {
+    type Item = Result<Vec<T>, FlannError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut point = Vec::new();
+        match self.read_into(&mut point) {
+            Ok(true) => Some(Ok(point)),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Fills `buf`, returning `false` if the reader ended before any byte was read.
+fn read_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, FlannError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(FlannError::InvalidDataset),
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => return Err(FlannError::FailedToReadDataset),
+        }
+    }
+    Ok(true)
+}
+
+/// Writes points that are in a slice of memory in component order where
+/// there are `point_len` components.
+pub fn write_vecs<T: VecsElement, W: Write>(
+    writer: &mut W,
+    point_len: usize,
+    points: &[T],
+) -> Result<(), FlannError> {
+    if point_len == 0 || point_len > i32::MAX as usize || points.len() % point_len != 0 {
+        return Err(FlannError::InvalidFlatPointsLen {
+            expected: point_len,
+            got: points.len(),
+        });
+    }
+    let mut record = Vec::with_capacity(4 + point_len * T::SIZE);
+    for point in points.chunks(point_len) {
+        record.clear();
+        record.extend_from_slice(&(point_len as i32).to_le_bytes());
+        for &component in point {
+            component.extend_le_bytes(&mut record);
+        }
+        writer
+            .write_all(&record)
+            .map_err(|_| FlannError::FailedToWriteDataset)?;
+    }
+    writer.flush().map_err(|_| FlannError::FailedToWriteDataset)
+}
+
+/// Reads a whole `.fvecs`, `.ivecs` or `.bvecs` file.
+pub fn load_vecs<T: VecsElement, P: AsRef<Path>>(path: P) -> Result<Dataset<T>, FlannError> {
+    let file = File::open(path).map_err(|_| FlannError::FailedToReadDataset)?;
+    let file_len = file
+        .metadata()
+        .map_err(|_| FlannError::FailedToReadDataset)?
+        .len();
+    VecsReader::with_max_len(BufReader::new(file), file_len).read_all()
+}
+
+/// Writes points to a `.fvecs`, `.ivecs` or `.bvecs` file.
+///
+/// See `write_vecs` for the layout of `points`.
+pub fn save_vecs<T: VecsElement, P: AsRef<Path>>(
+    path: P,
+    point_len: usize,
+    points: &[T],
+) -> Result<(), FlannError> {
+    let file = File::create(path).map_err(|_| FlannError::FailedToWriteDataset)?;
+    write_vecs(&mut BufWriter::new(file), point_len, points)
+}
+
+pub fn load_fvecs<P: AsRef<Path>>(path: P) -> Result<Dataset<f32>, FlannError> {
+    load_vecs(path)
+}
+
+pub fn load_ivecs<P: AsRef<Path>>(path: P) -> Result<Dataset<i32>, FlannError> {
+    load_vecs(path)
+}
+
+pub fn load_bvecs<P: AsRef<Path>>(path: P) -> Result<Dataset<u8>, FlannError> {
+    load_vecs(path)
+}
+
+pub fn save_fvecs<P: AsRef<Path>>(
+    path: P,
+    point_len: usize,
+    points: &[f32],
+) -> Result<(), FlannError> {
+    save_vecs(path, point_len, points)
+}
+
+pub fn save_ivecs<P: AsRef<Path>>(
+    path: P,
+    point_len: usize,
+    points: &[i32],
+) -> Result<(), FlannError> {
+    save_vecs(path, point_len, points)
+}
+
+pub fn save_bvecs<P: AsRef<Path>>(
+    path: P,
+    point_len: usize,
+    points: &[u8],
+) -> Result<(), FlannError> {
+    save_vecs(path, point_len, points)
+}
+
+/// A memory-mapped `.fvecs`, `.ivecs` or `.bvecs` file.
+///
+/// Only the record headers are read when the file is opened, so points of
+/// large files can be read as they are needed.
+#[cfg(feature = "memmap2")]
+pub struct MappedVecs<T> {
+    map: Mmap,
+    point_len: usize,
+    len: usize,
+    _phantom: PhantomData<T>,
+}
+
+#[cfg(feature = "memmap2")]
+impl<T: VecsElement> MappedVecs<T> {
+    /// Maps a file and checks that all records have the same number of components.
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FlannError> {
+        let file = File::open(path).map_err(|_| FlannError::FailedToReadDataset)?;
+        let map = unsafe { Mmap::map(&file) }.map_err(|_| FlannError::FailedToReadDataset)?;
+        if map.is_empty() {
+            return Ok(Self {
+                map,
+                point_len: 0,
+                len: 0,
+                _phantom: PhantomData,
+            });
+        }
+        let point_len = read_header(&map, 0)?;
+        let record_len = 4 + point_len * T::SIZE;
+        if map.len() % record_len != 0 {
+            return Err(FlannError::InvalidDataset);
+        }
+        let len = map.len() / record_len;
+        for idx in 1..len {
+            let got = read_header(&map, idx * record_len)?;
+            if got != point_len {
+                return Err(FlannError::InvalidPointDimensionality {
+                    expected: point_len,
+                    got,
+                });
+            }
+        }
+        Ok(Self {
+            map,
+            point_len,
+            len,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn point_len(&self) -> usize {
+        self.point_len
+    }
+
+    /// The number of points.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies the point that corresponds to this index `idx`.
+    pub fn point(&self, idx: usize) -> Option<Vec<T>> {
+        self.points(idx..idx + 1)
+    }
+
+    /// Copies the points in `range` into the flat layout.
+    ///
+    /// Returns `None` if the range is out of bounds.
+    pub fn points(&self, range: Range<usize>) -> Option<Vec<T>> {
+        if range.start > range.end || range.end > self.len {
+            return None;
+        }
+        let record_len = 4 + self.point_len * T::SIZE;
+        let mut points = Vec::with_capacity(range.len() * self.point_len);
+        for idx in range {
+            let start = idx * record_len + 4;
+            let record = &self.map[start..start + self.point_len * T::SIZE];
+            points.extend(record.chunks(T::SIZE).map(T::from_le_slice));
+        }
+        Some(points)
+    }
+
+    /// Copies all points.
+    pub fn to_dataset(&self) -> Dataset<T> {
+        Dataset {
+            point_len: self.point_len,
+            points: self.points(0..self.len).unwrap(),
+        }
+    }
+}
+
+#[cfg(feature = "memmap2")]
+fn read_header(map: &[u8], offset: usize) -> Result<usize, FlannError> {
+    let header = map
+        .get(offset..offset + 4)
+        .ok_or(FlannError::InvalidDataset)?;
+    let len = i32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    if len <= 0 {
+        return Err(FlannError::InvalidDataset);
+    }
+    Ok(len as usize)
+}
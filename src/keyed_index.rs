@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use vec_index::VecIndex;
+use FlannError;
+use Indexable;
+use Neighbor;
+use Parameters;
+
+/// A neighbor found by a `KeyedIndex`.
+#[derive(Copy, Clone, Debug)]
+pub struct KeyedNeighbor<'a, K: 'a, D> {
+    pub key: &'a K,
+    /// See `Neighbor::distance`.
+    pub distance: D,
+}
+
+/// An index whose points are referred to by keys of the user's choosing
+/// instead of FLANN's positional ids.
+pub struct KeyedIndex<K, T: Indexable + 'static> {
+    point_len: usize,
+    parameters: Parameters,
+    /// This is `None` until the first point is inserted, since FLANN can't
+    /// build an index without points.
+    index: Option<VecIndex<T>>,
+    ids: HashMap<K, usize>,
+    /// The key of every FLANN id, or `None` if the point was removed.
+    keys: Vec<Option<K>>,
+}
+
+impl<K: Eq + Hash + Clone, T: Indexable> KeyedIndex<K, T> {
+    /// Makes a new empty index for points with `point_len` components.
+    pub fn new(point_len: usize, parameters: Parameters) -> Result<Self, FlannError> {
+        parameters.validate()?;
+        Ok(Self {
+            point_len,
+            parameters,
+            index: None,
+            ids: HashMap::new(),
+            keys: Vec::new(),
+        })
+    }
+
+    /// Inserts a point, replacing the point of `key` if there already is one.
+    pub fn insert(&mut self, key: K, point: Vec<T>) -> Result<(), FlannError> {
+        if point.len() != self.point_len {
+            return Err(FlannError::InvalidPointDimensionality {
+                expected: self.point_len,
+                got: point.len(),
+            });
+        }
+        match self.index {
+            Some(ref mut index) => index.add(point)?,
+            None => {
+                self.index = Some(VecIndex::new(
+                    self.point_len,
+                    Some(point),
+                    self.parameters.clone(),
+                )?)
+            }
+        }
+        let id = self.keys.len();
+        if let Some(old_id) = self.ids.get(&key).cloned() {
+            if let Err(error) = self.remove_id(old_id) {
+                // FLANN has the new point, so `keys` needs an entry to keep its ids
+                // in step, but searches skip it and the old point stays.
+                self.keys.push(None);
+                return Err(error);
+            }
+        }
+        self.keys.push(Some(key.clone()));
+        self.ids.insert(key, id);
+        Ok(())
+    }
+
+    /// Removes the point of `key`.
+    ///
+    /// Returns `false` if there was no such point.
+    pub fn remove(&mut self, key: &K) -> Result<bool, FlannError> {
+        match self.ids.get(key).cloned() {
+            Some(id) => {
+                self.remove_id(id)?;
+                self.ids.remove(key);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn remove_id(&mut self, id: usize) -> Result<(), FlannError> {
+        self.index.as_mut().unwrap().remove(id)?;
+        self.keys[id] = None;
+        Ok(())
+    }
+
+    /// Get the point of `key`.
+    pub fn get(&self, key: &K) -> Option<&[T]> {
+        let id = *self.ids.get(key)?;
+        self.index.as_ref()?.get(id)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.ids.contains_key(key)
+    }
+
+    /// Returns the FLANN id of the point of `key`.
+    pub fn id(&self, key: &K) -> Option<usize> {
+        self.ids.get(key).cloned()
+    }
+
+    /// Returns the key of the point with the FLANN id `id`.
+    pub fn key(&self, id: usize) -> Option<&K> {
+        self.keys.get(id).and_then(Option::as_ref)
+    }
+
+    /// The number of points that weren't removed.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Performs a search to find only the closest neighbor.
+    ///
+    /// Returns `None` if the index has no points.
+    pub fn find_nearest_neighbor(
+        &self,
+        point: &[T],
+    ) -> Result<Option<KeyedNeighbor<'_, K, T::ResultType>>, FlannError> {
+        Ok(self.find_nearest_neighbors(1, point)?.next())
+    }
+
+    /// Performs k-NN search for `num` neighbors.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    pub fn find_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<impl Iterator<Item = KeyedNeighbor<'_, K, T::ResultType>>, FlannError> {
+        let neighbors = match self.index {
+            Some(ref index) if !self.is_empty() => {
+                index.find_nearest_neighbors(num, point)?.collect()
+            }
+            _ => Vec::new(),
+        };
+        Ok(self.keyed(neighbors, num))
+    }
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    pub fn find_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<impl Iterator<Item = KeyedNeighbor<'_, K, T::ResultType>>, FlannError> {
+        let neighbors = match self.index {
+            Some(ref index) if !self.is_empty() => index
+                .find_nearest_neighbors_radius(num, radius, point)?
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(self.keyed(neighbors, num))
+    }
+
+    /// Looks up the keys of neighbors. FLANN doesn't return removed points,
+    /// but points without a key are skipped in case it does.
+    fn keyed(
+        &self,
+        neighbors: Vec<Neighbor<T::ResultType>>,
+        num: usize,
+    ) -> impl Iterator<Item = KeyedNeighbor<'_, K, T::ResultType>> {
+        neighbors
+            .into_iter()
+            .filter_map(move |neighbor| {
+                self.key(neighbor.index).map(|key| KeyedNeighbor {
+                    key,
+                    distance: neighbor.distance,
+                })
+            })
+            .take(num)
+    }
+}
@@ -0,0 +1,131 @@
+use Neighbor;
+
+/// The `k` nearest neighbors of several points.
+///
+/// The neighbors of all points are stored in one flat array with `k`
+/// neighbors per point, each sorted by closest to furthest.
+#[derive(Clone, Debug)]
+pub struct KnnResults<D> {
+    k: usize,
+    len: usize,
+    neighbors: Vec<Neighbor<D>>,
+}
+
+impl<D> KnnResults<D> {
+    /// Makes results of `len` points from flat arrays with `k` neighbors per point.
+    pub(crate) fn from_flat(k: usize, len: usize, indices: Vec<usize>, distances: Vec<D>) -> Self {
+        let neighbors = indices
+            .into_iter()
+            .zip(distances)
+            .map(|(index, distance)| Neighbor { index, distance })
+            .collect();
+        Self::new(k, len, neighbors)
+    }
+
+    pub(crate) fn new(k: usize, len: usize, neighbors: Vec<Neighbor<D>>) -> Self {
+        debug_assert_eq!(neighbors.len(), k * len);
+        Self { k, len, neighbors }
+    }
+
+    /// Concatenates the results of consecutive chunks of points.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn concat(k: usize, chunks: Vec<Self>) -> Self {
+        let len = chunks.iter().map(|chunk| chunk.len).sum();
+        let mut neighbors = Vec::with_capacity(k * len);
+        for chunk in chunks {
+            neighbors.extend(chunk.neighbors);
+        }
+        Self::new(k, len, neighbors)
+    }
+
+    /// The number of neighbors of every point.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The number of points that were searched for.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the neighbors of the point `query`.
+    ///
+    /// Panics if `query` is out of bounds.
+    pub fn row(&self, query: usize) -> &[Neighbor<D>] {
+        self.get(query).expect("query out of bounds")
+    }
+
+    /// Get the neighbors of the point `query`, or `None` if it is out of bounds.
+    pub fn get(&self, query: usize) -> Option<&[Neighbor<D>]> {
+        if query < self.len {
+            Some(&self.neighbors[query * self.k..(query + 1) * self.k])
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the neighbors of every point.
+    pub fn iter(&self) -> KnnRows<'_, D> {
+        KnnRows {
+            results: self,
+            query: 0,
+        }
+    }
+
+    /// The neighbors of all points in one flat array.
+    pub fn neighbors(&self) -> &[Neighbor<D>] {
+        &self.neighbors
+    }
+
+    /// Splits the results into the neighbors of every point.
+    pub fn into_vecs(self) -> Vec<Vec<Neighbor<D>>> {
+        let k = self.k;
+        let mut neighbors = self.neighbors.into_iter();
+        (0..self.len)
+            .map(|_| neighbors.by_ref().take(k).collect())
+            .collect()
+    }
+}
+
+impl<D> From<KnnResults<D>> for Vec<Vec<Neighbor<D>>> {
+    fn from(results: KnnResults<D>) -> Self {
+        results.into_vecs()
+    }
+}
+
+impl<'a, D> IntoIterator for &'a KnnResults<D> {
+    type Item = &'a [Neighbor<D>];
+    type IntoIter = KnnRows<'a, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the neighbors of every point of `KnnResults`.
+#[derive(Clone, Debug)]
+pub struct KnnRows<'a, D: 'a> {
+    results: &'a KnnResults<D>,
+    query: usize,
+}
+
+impl<'a, D> Iterator for KnnRows<'a, D> {
+    type Item = &'a [Neighbor<D>];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.results.get(self.query)?;
+        self.query += 1;
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.results.len - self.query;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, D> ExactSizeIterator for KnnRows<'a, D> {}
@@ -6,23 +6,69 @@ extern crate generic_array;
 #[macro_use]
 extern crate failure;
 pub extern crate flann_sys as raw;
-extern crate itertools;
+#[cfg(feature = "memmap2")]
+extern crate memmap2;
+#[cfg(feature = "nalgebra")]
+extern crate nalgebra;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
+mod array_index;
+mod binary_index;
+mod build_report;
+mod bundle;
+mod clustering;
+mod config;
+mod distance;
 mod enums;
 mod index;
 mod indexable;
 mod indices;
+pub mod io;
+mod keyed_index;
+mod knn_results;
+mod linear_index;
+#[cfg(feature = "nalgebra")]
+mod nalgebra_support;
+#[cfg(feature = "ndarray")]
+mod ndarray_support;
+mod nearest_neighbor_search;
 mod parameters;
+mod radius_neighbors;
+mod recall;
+mod saved_index;
+mod search_buffers;
 mod slice_index;
 mod vec_index;
+mod vocabulary;
 
+pub use array_index::ArrayIndex;
+pub use binary_index::BinaryIndex;
+pub use build_report::BuildReport;
+pub use clustering::{compute_cluster_centers, compute_cluster_centers_array};
+pub use config::{
+    AutotunedParams, CompositeParams, HierarchicalParams, IndexConfig, KDTreeParams,
+    KDTreeSingleParams, KMeansParams, LshParams,
+};
 pub use enums::{Algorithm, CentersInit, Checks, DistanceType, LogLevel};
 pub use generic_array::typenum;
 pub use index::Index;
 pub use indexable::Indexable;
-pub use parameters::Parameters;
+pub use keyed_index::{KeyedIndex, KeyedNeighbor};
+pub use knn_results::{KnnResults, KnnRows};
+pub use linear_index::{ExactDistance, LinearIndex};
+#[cfg(feature = "nalgebra")]
+pub use nalgebra_support::StaticPoint;
+pub use nearest_neighbor_search::NearestNeighborSearch;
+pub use parameters::{Parameters, SearchParams};
+pub use radius_neighbors::{Radii, RadiusNeighbors};
+pub use recall::{evaluate_recall, LatencyStats, RecallReport};
+pub use search_buffers::SearchBuffers;
 pub use slice_index::SliceIndex;
 pub use vec_index::VecIndex;
+pub use vocabulary::Vocabulary;
 
 #[derive(Copy, Clone, Debug, Fail)]
 pub enum FlannError {
@@ -40,10 +86,82 @@ pub enum FlannError {
     FailedToBuildIndex,
     #[fail(display = "input must have at least one point")]
     ZeroInputPoints,
+    #[fail(display = "FLANN failed to add points to index")]
+    AddPointsFailed,
+    #[fail(display = "FLANN failed to remove point {} from index", idx)]
+    RemoveFailed { idx: usize },
+    #[fail(display = "FLANN failed to search index")]
+    SearchFailed,
+    #[fail(display = "must request at least one cluster")]
+    ZeroClusters,
+    #[fail(display = "FLANN failed to compute cluster centers")]
+    ClusteringFailed,
+    #[fail(display = "expected {} points, but got {} points", expected, got)]
+    InvalidPointCount { expected: usize, got: usize },
+    #[fail(display = "path must be valid UTF-8 without nul bytes")]
+    InvalidPath,
+    #[fail(display = "file is not a saved FLANN index")]
+    InvalidSavedIndex,
+    #[fail(display = "FLANN failed to save index")]
+    FailedToSaveIndex,
+    #[fail(display = "FLANN failed to load index")]
+    FailedToLoadIndex,
+    #[fail(display = "failed to write bundle")]
+    FailedToWriteBundle,
+    #[fail(display = "failed to read bundle")]
+    FailedToReadBundle,
+    #[fail(display = "file is not a valid bundle or is corrupted")]
+    InvalidBundle,
+    #[fail(display = "bundle format version {} is not supported", version)]
+    UnsupportedBundleVersion { version: u32 },
+    #[fail(display = "bundle was written on a platform with a different memory layout")]
+    IncompatibleBundle,
+    #[fail(
+        display = "expected bundle with FLANN data type {}, but got data type {}",
+        expected, got
+    )]
+    BundleDataTypeMismatch { expected: i32, got: i32 },
+    #[fail(
+        display = "distance type {:?} is not supported by the FLANN C bindings",
+        distance_type
+    )]
+    UnsupportedDistanceType { distance_type: DistanceType },
+    #[fail(display = "algorithm {:?} can't be used for this index", algorithm)]
+    InvalidAlgorithm { algorithm: Algorithm },
+    #[fail(display = "invalid parameter {}: {}", parameter, requirement)]
+    InvalidParameter {
+        parameter: &'static str,
+        requirement: &'static str,
+    },
+    #[fail(display = "failed to read dataset")]
+    FailedToReadDataset,
+    #[fail(display = "failed to write dataset")]
+    FailedToWriteDataset,
+    #[fail(display = "dataset file is malformed")]
+    InvalidDataset,
+    #[fail(display = "dataset has a different element type")]
+    DatasetTypeMismatch,
+    #[fail(display = "index does not support {}", operation)]
+    UnsupportedOperation { operation: &'static str },
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct Neighbor<D> {
     pub index: usize,
-    pub distance_squared: D,
+    /// The distance to the point in the metric the index was built with.
+    ///
+    /// FLANN doesn't take the root of distances, so for `DistanceType::Euclidean`
+    /// this is the squared distance and for `DistanceType::Minkowski` the sum of
+    /// the `distance_order`-th powers.
+    pub distance: D,
+}
+
+/// The neighbors found by a radius search that returns at most a given
+/// number of them.
+#[derive(Clone, Debug)]
+pub struct RadiusResult<D> {
+    /// The neighbors sorted by closest to furthest.
+    pub neighbors: Vec<Neighbor<D>>,
+    /// Whether there were more points within the radius than were returned.
+    pub truncated: bool,
 }
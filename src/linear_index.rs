@@ -0,0 +1,320 @@
+use enums::DistanceType;
+use knn_results::KnnResults;
+use FlannError;
+use Indexable;
+use Neighbor;
+use Parameters;
+use RadiusResult;
+
+/// Element types whose distances `LinearIndex` can compute in Rust.
+pub trait ExactDistance: Indexable {
+    fn to_f64(&self) -> f64;
+
+    /// Converts a distance computed in Rust to `ResultType`.
+    fn distance_from_f64(distance: f64) -> Self::ResultType;
+
+    fn distance_to_f64(distance: &Self::ResultType) -> f64;
+}
+
+macro_rules! impl_exact_distance {
+    ($t:ty, $r:ty) => {
+        impl ExactDistance for $t {
+            #[inline]
+            fn to_f64(&self) -> f64 {
+                f64::from(*self)
+            }
+
+            #[inline]
+            fn distance_from_f64(distance: f64) -> $r {
+                distance as $r
+            }
+
+            #[inline]
+            fn distance_to_f64(distance: &$r) -> f64 {
+                f64::from(*distance)
+            }
+        }
+    };
+}
+
+impl_exact_distance!(f32, f32);
+impl_exact_distance!(f64, f64);
+impl_exact_distance!(u8, f32);
+impl_exact_distance!(i32, f32);
+
+/// An exact index written in Rust that compares every query with every point.
+///
+/// Distances are computed like FLANN computes them, including leaving out
+/// roots, so results can serve as ground truth for a FLANN index built with
+/// the same `distance_type`. Hamming distances treat components as integers.
+pub struct LinearIndex<T: ExactDistance> {
+    points: Vec<T>,
+    pub(crate) point_len: usize,
+    distance_type: DistanceType,
+    distance_order: i32,
+    /// Whether the point with every id was removed.
+    removed: Vec<bool>,
+    num_removed: usize,
+}
+
+impl<T: ExactDistance> LinearIndex<T> {
+    /// Makes a new index from points that are in a `Vec` in component order
+    /// where there are `point_len` components.
+    ///
+    /// Only `distance_type` and `distance_order` of `parameters` are used.
+    pub fn new(
+        point_len: usize,
+        points: Vec<T>,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError> {
+        if points.is_empty() {
+            return Err(FlannError::ZeroInputPoints);
+        }
+        if point_len == 0 || points.len() % point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: point_len,
+                got: points.len(),
+            });
+        }
+        Ok(Self {
+            removed: vec![false; points.len() / point_len],
+            num_removed: 0,
+            points,
+            point_len,
+            distance_type: parameters.distance_type,
+            distance_order: parameters.distance_order,
+        })
+    }
+
+    /// Adds a point to the index.
+    pub fn add_slice(&mut self, point: &[T]) -> Result<(), FlannError> {
+        self.check_point(point)?;
+        self.points.extend_from_slice(point);
+        self.removed.push(false);
+        Ok(())
+    }
+
+    /// Adds multiple points to the index.
+    pub fn add_many_slices(&mut self, points: &[T]) -> Result<(), FlannError> {
+        if points.len() % self.point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: self.point_len,
+                got: points.len(),
+            });
+        }
+        self.points.extend_from_slice(points);
+        let len = self.removed.len() + points.len() / self.point_len;
+        self.removed.resize(len, false);
+        Ok(())
+    }
+
+    /// Removes a point at index `idx`, which searches won't find anymore.
+    ///
+    /// Like in FLANN, the ids of the other points don't change.
+    pub fn remove(&mut self, idx: usize) -> Result<(), FlannError> {
+        match self.removed.get_mut(idx) {
+            Some(removed) => {
+                if !*removed {
+                    *removed = true;
+                    self.num_removed += 1;
+                }
+                Ok(())
+            }
+            None => Err(FlannError::RemoveFailed { idx }),
+        }
+    }
+
+    /// Get the point that corresponds to this index `idx`.
+    ///
+    /// Returns `None` if there is no such point or it was removed.
+    pub fn get(&self, idx: usize) -> Option<&[T]> {
+        if self.removed.get(idx) == Some(&false) {
+            self.points.chunks(self.point_len).nth(idx)
+        } else {
+            None
+        }
+    }
+
+    /// The number of points that weren't removed, like FLANN counts them.
+    pub fn len(&self) -> usize {
+        self.live_len()
+    }
+
+    /// The number of points that weren't removed.
+    pub fn live_len(&self) -> usize {
+        self.removed.len() - self.num_removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Performs a search to find only the closest neighbor.
+    pub fn find_nearest_neighbor(
+        &self,
+        point: &[T],
+    ) -> Result<Neighbor<T::ResultType>, FlannError> {
+        self.check_point(point)?;
+        self.nearest(1, point, None)
+            .pop()
+            .ok_or(FlannError::SearchFailed)
+    }
+
+    /// Performs k-NN search for `num` neighbors.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    ///
+    /// The returned iterator is sorted by closest to furthest.
+    pub fn find_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.check_point(point)?;
+        Ok(self.nearest(num, point, None).into_iter())
+    }
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    ///
+    /// The returned iterator is sorted by closest to furthest.
+    pub fn find_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.check_point(point)?;
+        Ok(self.nearest(num, point, Some(radius.into())).into_iter())
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// and reports whether there were more.
+    pub fn find_within_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        self.check_point(point)?;
+        let mut neighbors = self.nearest(num.saturating_add(1), point, Some(radius.into()));
+        let truncated = neighbors.len() > num;
+        neighbors.truncate(num);
+        Ok(RadiusResult {
+            neighbors,
+            truncated,
+        })
+    }
+
+    /// Performs a search for all neighbors within `radius` distance.
+    ///
+    /// The neighbors are sorted by closest to furthest.
+    pub fn find_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.check_point(point)?;
+        Ok(self.nearest(usize::MAX, point, Some(radius.into())))
+    }
+
+    /// Performs k-NN search on `num` neighbors for several points.
+    ///
+    /// If there are less points in the set than `num` it returns that many
+    /// neighbors for each point.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    pub fn find_many_nearest_neighbors_flat(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        if points.len() % self.point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: self.point_len,
+                got: points.len(),
+            });
+        }
+        let num = num.min(self.live_len());
+        let neighbors = points
+            .chunks(self.point_len)
+            .flat_map(|point| self.nearest(num, point, None))
+            .collect();
+        Ok(KnnResults::new(
+            num,
+            points.len() / self.point_len,
+            neighbors,
+        ))
+    }
+
+    fn check_point(&self, point: &[T]) -> Result<(), FlannError> {
+        if point.len() != self.point_len {
+            return Err(FlannError::InvalidPointDimensionality {
+                expected: self.point_len,
+                got: point.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Finds the `num` closest points that are within `radius`, if there is one,
+    /// skipping removed points.
+    ///
+    /// Like FLANN, points exactly at `radius` are not within it. Points with
+    /// NaN distances are ordered last and are never within a radius.
+    fn nearest(
+        &self,
+        num: usize,
+        point: &[T],
+        radius: Option<f64>,
+    ) -> Vec<Neighbor<T::ResultType>> {
+        let mut neighbors = self
+            .points
+            .chunks(self.point_len)
+            .map(|other| distance(self.distance_type, self.distance_order, point, other))
+            .enumerate()
+            .filter(|&(index, _)| !self.removed[index])
+            .filter(|&(_, distance)| radius.is_none_or(|radius| distance < radius))
+            .collect::<Vec<_>>();
+        neighbors.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or_else(|| a.1.is_nan().cmp(&b.1.is_nan()))
+        });
+        neighbors
+            .into_iter()
+            .take(num)
+            .map(|(index, distance)| Neighbor {
+                index,
+                distance: T::distance_from_f64(distance),
+            })
+            .collect()
+    }
+}
+
+/// Computes the distance between two points the way FLANN does.
+fn distance<T: ExactDistance>(distance_type: DistanceType, order: i32, a: &[T], b: &[T]) -> f64 {
+    let pairs = a.iter().zip(b).map(|(a, b)| (a.to_f64(), b.to_f64()));
+    match distance_type {
+        DistanceType::Euclidean | DistanceType::L2 | DistanceType::L2Simple => {
+            pairs.map(|(a, b)| (a - b) * (a - b)).sum()
+        }
+        DistanceType::Manhattan | DistanceType::L1 => pairs.map(|(a, b)| (a - b).abs()).sum(),
+        DistanceType::Minkowski => pairs.map(|(a, b)| (a - b).abs().powi(order)).sum(),
+        DistanceType::Max => pairs.map(|(a, b)| (a - b).abs()).fold(0.0, f64::max),
+        DistanceType::HistIntersect => pairs.map(|(a, b)| a.min(b)).sum(),
+        DistanceType::Hellinger => pairs
+            .map(|(a, b)| (a.sqrt() - b.sqrt()) * (a.sqrt() - b.sqrt()))
+            .sum(),
+        DistanceType::ChiSquare => pairs
+            .filter(|&(a, b)| a + b > 0.0)
+            .map(|(a, b)| (a - b) * (a - b) / (a + b))
+            .sum(),
+        DistanceType::KullbackLeibler => pairs
+            .filter(|&(a, b)| b != 0.0 && a / b > 0.0)
+            .map(|(a, b)| a * (a / b).ln())
+            .sum(),
+        DistanceType::Hamming | DistanceType::HammingLut | DistanceType::HammingPopcnt => pairs
+            .map(|(a, b)| f64::from(((a as i64 ^ b as i64) as u32).count_ones()))
+            .sum(),
+    }
+}
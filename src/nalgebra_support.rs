@@ -0,0 +1,148 @@
+use generic_array::{ArrayLength, GenericArray};
+use knn_results::KnnResults;
+use nalgebra::allocator::Allocator;
+use nalgebra::{DefaultAllocator, DimName, Point, Scalar, VectorN};
+use FlannError;
+use Index;
+use Indexable;
+use Neighbor;
+use Parameters;
+
+/// A statically sized point with the same memory layout as `GenericArray<T, N>`,
+/// so that it can be passed to an `Index` without copying.
+///
+/// # Safety
+///
+/// References to points are reinterpreted as references to `GenericArray`s
+/// and back, and slices of points as slices of `GenericArray`s. Implementors
+/// must store exactly `N` components contiguously like `[T; N]`, with no other
+/// fields and the same size and alignment as `[T; N]`, and every value of
+/// `[T; N]` must be a valid point.
+pub unsafe trait StaticPoint<T, N: ArrayLength<T>>: Sized {
+    fn as_generic_array(&self) -> &GenericArray<T, N> {
+        assert_same_layout::<T, N, Self>();
+        unsafe { &*(self as *const Self as *const GenericArray<T, N>) }
+    }
+
+    fn from_generic_array(array: &GenericArray<T, N>) -> &Self {
+        assert_same_layout::<T, N, Self>();
+        unsafe { &*(array as *const GenericArray<T, N> as *const Self) }
+    }
+}
+
+/// Checks the size and alignment that `StaticPoint` requires, which compiles
+/// to nothing for correct implementations.
+fn assert_same_layout<T, N: ArrayLength<T>, P: StaticPoint<T, N>>() {
+    assert_eq!(
+        std::mem::size_of::<P>(),
+        std::mem::size_of::<GenericArray<T, N>>()
+    );
+    assert_eq!(
+        std::mem::align_of::<P>(),
+        std::mem::align_of::<GenericArray<T, N>>()
+    );
+}
+
+// Statically sized matrices store their components in a `GenericArray` and
+// points only wrap their coordinate vector, both with `#[repr(C)]`.
+unsafe impl<T, D> StaticPoint<T, D::Value> for Point<T, D>
+where
+    T: Scalar,
+    D: DimName,
+    D::Value: ArrayLength<T>,
+    DefaultAllocator: Allocator<T, D>,
+{
+}
+
+unsafe impl<T, D> StaticPoint<T, D::Value> for VectorN<T, D>
+where
+    T: Scalar,
+    D: DimName,
+    D::Value: ArrayLength<T>,
+    DefaultAllocator: Allocator<T, D>,
+{
+}
+
+impl<T: Indexable, N: ArrayLength<T>> Index<T, N> {
+    /// Makes a new index from `nalgebra` points or vectors.
+    pub fn from_points<I, P>(points: I, parameters: Parameters) -> Result<Self, FlannError>
+    where
+        I: IntoIterator<Item = P>,
+        P: StaticPoint<T, N>,
+    {
+        Self::new(
+            points
+                .into_iter()
+                .map(|point| point.as_generic_array().clone()),
+            parameters,
+        )
+    }
+
+    /// Adds a `nalgebra` point or vector to the index.
+    pub fn add_point<P: StaticPoint<T, N>>(&mut self, point: &P) -> Result<(), FlannError> {
+        self.add(point.as_generic_array().clone())
+    }
+
+    /// Adds multiple `nalgebra` points or vectors to the index.
+    pub fn add_points<I, P>(&mut self, points: I) -> Result<(), FlannError>
+    where
+        I: IntoIterator<Item = P>,
+        P: StaticPoint<T, N>,
+    {
+        self.add_multiple(
+            points
+                .into_iter()
+                .map(|point| point.as_generic_array().clone()),
+        )
+    }
+
+    /// Get the point that corresponds to this index `idx` as a `nalgebra`
+    /// point or vector, for example to look up the points of neighbors.
+    pub fn get_point<P: StaticPoint<T, N>>(&self, idx: usize) -> Option<&P> {
+        self.get(idx).map(P::from_generic_array)
+    }
+
+    /// Performs a search to find only the closest neighbor of a `nalgebra`
+    /// point or vector.
+    pub fn find_nearest_neighbor_point<P: StaticPoint<T, N>>(
+        &self,
+        point: &P,
+    ) -> Result<Neighbor<T::ResultType>, FlannError> {
+        self.find_nearest_neighbor(point.as_generic_array())
+    }
+
+    /// Performs k-NN search for `num` neighbors of a `nalgebra` point or vector.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    pub fn find_nearest_neighbors_point<P: StaticPoint<T, N>>(
+        &self,
+        num: usize,
+        point: &P,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.find_nearest_neighbors(num, point.as_generic_array())
+    }
+
+    /// Performs k-NN search for `num` neighbors of a `nalgebra` point or vector,
+    /// limiting the search to `radius` distance.
+    pub fn find_nearest_neighbors_radius_point<P: StaticPoint<T, N>>(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &P,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        self.find_nearest_neighbors_radius(num, radius, point.as_generic_array())
+    }
+
+    /// Performs k-NN search on `num` neighbors for several `nalgebra` points
+    /// or vectors without copying them.
+    pub fn find_many_nearest_neighbors_points<P: StaticPoint<T, N>>(
+        &self,
+        num: usize,
+        points: &[P],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        assert_same_layout::<T, N, P>();
+        let points = unsafe {
+            std::slice::from_raw_parts(points.as_ptr() as *const GenericArray<T, N>, points.len())
+        };
+        self.find_many_nearest_neighbors(num, points)
+    }
+}
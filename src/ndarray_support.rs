@@ -0,0 +1,56 @@
+use ndarray::{Array2, ArrayView2};
+use slice_index::SliceIndex;
+use FlannError;
+use Indexable;
+use Parameters;
+
+impl<'a, T: Indexable> SliceIndex<'a, T> {
+    /// Makes a new index from the rows of `points`.
+    ///
+    /// Arrays in standard layout are borrowed internally in FLANN like in
+    /// `new`. Other arrays are copied into the index first.
+    pub fn from_array_view(
+        points: ArrayView2<'a, T>,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError> {
+        let point_len = points.ncols();
+        match points.to_slice() {
+            Some(points) => Self::new(point_len, points, parameters),
+            None => Self::from_owned(point_len, points.iter().cloned().collect(), parameters),
+        }
+    }
+
+    /// Performs k-NN search for `k` neighbors for every row of `queries`.
+    ///
+    /// Returns the indices and distances of the neighbors with one row per
+    /// query. If there are less points in the index than `k` there are only
+    /// that many columns.
+    pub fn search_knn_array(
+        &self,
+        queries: &ArrayView2<T>,
+        k: usize,
+    ) -> Result<(Array2<usize>, Array2<T::ResultType>), FlannError> {
+        if queries.ncols() != self.point_len {
+            return Err(FlannError::InvalidPointDimensionality {
+                expected: self.point_len,
+                got: queries.ncols(),
+            });
+        }
+        let num_queries = queries.nrows();
+        let k = k.min(self.len());
+        if k == 0 || num_queries == 0 {
+            return Ok((
+                Array2::default((num_queries, k)),
+                Array2::default((num_queries, k)),
+            ));
+        }
+        let queries = queries.as_standard_layout();
+        let mut indices = vec![0; num_queries * k];
+        let mut distances = vec![T::ResultType::default(); num_queries * k];
+        self.knn_search_into(queries.as_slice().unwrap(), k, &mut indices, &mut distances)?;
+        Ok((
+            Array2::from_shape_vec((num_queries, k), indices).unwrap(),
+            Array2::from_shape_vec((num_queries, k), distances).unwrap(),
+        ))
+    }
+}
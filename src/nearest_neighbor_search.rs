@@ -0,0 +1,344 @@
+use generic_array::{ArrayLength, GenericArray};
+use knn_results::KnnResults;
+use linear_index::{ExactDistance, LinearIndex};
+use slice_index::SliceIndex;
+use vec_index::VecIndex;
+use FlannError;
+use Index;
+use Indexable;
+use Neighbor;
+
+/// The searches that every index supports, so that code can be written once
+/// for any index and indices can be swapped at runtime as trait objects.
+///
+/// Points are slices with `point_len` components. Methods that take or return
+/// points differently from the inherent ones have other names so that the
+/// inherent ones don't shadow them.
+pub trait NearestNeighborSearch<T: Indexable> {
+    /// The number of components of every point.
+    fn point_len(&self) -> usize;
+
+    /// The number of points in the index that weren't removed.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the point that corresponds to this index `idx`.
+    fn point(&self, idx: usize) -> Option<&[T]>;
+
+    /// Adds a copy of a point to the index.
+    ///
+    /// Indices that only borrow their points return
+    /// `FlannError::UnsupportedOperation`.
+    fn insert_point(&mut self, point: &[T]) -> Result<(), FlannError>;
+
+    /// Removes the point at index `idx` so that searches no longer find it.
+    fn delete_point(&mut self, idx: usize) -> Result<(), FlannError>;
+
+    /// Performs a search to find only the closest neighbor.
+    fn search_nearest_neighbor(&self, point: &[T]) -> Result<Neighbor<T::ResultType>, FlannError>;
+
+    /// Performs k-NN search for `num` neighbors.
+    /// If there are less points in the set than `num` it returns that many neighbors.
+    fn search_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError>;
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance.
+    fn search_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError>;
+
+    /// Performs a search for all neighbors within `radius` distance.
+    fn search_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError>;
+
+    /// Performs k-NN search on `num` neighbors for several points in a slice
+    /// of memory in component order.
+    fn search_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError>;
+}
+
+impl<'a, T: Indexable> NearestNeighborSearch<T> for SliceIndex<'a, T> {
+    fn point_len(&self) -> usize {
+        self.point_len
+    }
+
+    fn len(&self) -> usize {
+        self.live_len()
+    }
+
+    fn point(&self, idx: usize) -> Option<&[T]> {
+        self.get(idx)
+    }
+
+    fn insert_point(&mut self, _point: &[T]) -> Result<(), FlannError> {
+        // Points are borrowed for `'a`, which a copy made here wouldn't live for.
+        Err(FlannError::UnsupportedOperation {
+            operation: "adding points it doesn't borrow",
+        })
+    }
+
+    fn delete_point(&mut self, idx: usize) -> Result<(), FlannError> {
+        SliceIndex::remove(self, idx)
+    }
+
+    fn search_nearest_neighbor(&self, point: &[T]) -> Result<Neighbor<T::ResultType>, FlannError> {
+        self.find_nearest_neighbor(point)
+    }
+
+    fn search_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        Ok(self.find_nearest_neighbors(num, point)?.collect())
+    }
+
+    fn search_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        Ok(self
+            .find_nearest_neighbors_radius(num, radius, point)?
+            .collect())
+    }
+
+    fn search_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.find_all_within_radius(radius, point)
+    }
+
+    fn search_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        self.find_many_nearest_neighbors_flat(num, points)
+    }
+}
+
+impl<T: Indexable> NearestNeighborSearch<T> for VecIndex<T> {
+    fn point_len(&self) -> usize {
+        self.point_len
+    }
+
+    fn len(&self) -> usize {
+        self.live_len()
+    }
+
+    fn point(&self, idx: usize) -> Option<&[T]> {
+        self.get(idx)
+    }
+
+    fn insert_point(&mut self, point: &[T]) -> Result<(), FlannError> {
+        VecIndex::add(self, point.to_vec())
+    }
+
+    fn delete_point(&mut self, idx: usize) -> Result<(), FlannError> {
+        SliceIndex::remove(self, idx)
+    }
+
+    fn search_nearest_neighbor(&self, point: &[T]) -> Result<Neighbor<T::ResultType>, FlannError> {
+        (**self).search_nearest_neighbor(point)
+    }
+
+    fn search_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        (**self).search_nearest_neighbors(num, point)
+    }
+
+    fn search_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        (**self).search_nearest_neighbors_radius(num, radius, point)
+    }
+
+    fn search_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        (**self).search_all_within_radius(radius, point)
+    }
+
+    fn search_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        (**self).search_many_nearest_neighbors(num, points)
+    }
+}
+
+/// Reinterprets a point slice as a `GenericArray`.
+fn as_generic_array<T, N: ArrayLength<T>>(point: &[T]) -> Result<&GenericArray<T, N>, FlannError> {
+    if point.len() != N::to_usize() {
+        return Err(FlannError::InvalidPointDimensionality {
+            expected: N::to_usize(),
+            got: point.len(),
+        });
+    }
+    Ok(GenericArray::from_slice(point))
+}
+
+impl<T: Indexable, N: ArrayLength<T>> NearestNeighborSearch<T> for Index<T, N> {
+    fn point_len(&self) -> usize {
+        N::to_usize()
+    }
+
+    fn len(&self) -> usize {
+        Index::len(self)
+    }
+
+    fn point(&self, idx: usize) -> Option<&[T]> {
+        self.get(idx).map(|point| point.as_slice())
+    }
+
+    fn insert_point(&mut self, point: &[T]) -> Result<(), FlannError> {
+        Index::add(self, as_generic_array(point)?.clone())
+    }
+
+    fn delete_point(&mut self, idx: usize) -> Result<(), FlannError> {
+        Index::remove(self, idx)
+    }
+
+    fn search_nearest_neighbor(&self, point: &[T]) -> Result<Neighbor<T::ResultType>, FlannError> {
+        self.find_nearest_neighbor(as_generic_array(point)?)
+    }
+
+    fn search_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        Ok(self
+            .find_nearest_neighbors(num, as_generic_array(point)?)?
+            .collect())
+    }
+
+    fn search_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        Ok(self
+            .find_nearest_neighbors_radius(num, radius, as_generic_array(point)?)?
+            .collect())
+    }
+
+    fn search_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.find_all_within_radius(radius, as_generic_array(point)?)
+    }
+
+    fn search_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        if points.len() % N::to_usize() != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: N::to_usize(),
+                got: points.len(),
+            });
+        }
+        // `GenericArray<T, N>` has the same layout as `[T; N]`.
+        let points = unsafe {
+            std::slice::from_raw_parts(
+                points.as_ptr() as *const GenericArray<T, N>,
+                points.len() / N::to_usize(),
+            )
+        };
+        self.find_many_nearest_neighbors(num, points)
+    }
+}
+
+impl<T: ExactDistance> NearestNeighborSearch<T> for LinearIndex<T> {
+    fn point_len(&self) -> usize {
+        self.point_len
+    }
+
+    fn len(&self) -> usize {
+        LinearIndex::len(self)
+    }
+
+    fn point(&self, idx: usize) -> Option<&[T]> {
+        self.get(idx)
+    }
+
+    fn insert_point(&mut self, point: &[T]) -> Result<(), FlannError> {
+        self.add_slice(point)
+    }
+
+    fn delete_point(&mut self, idx: usize) -> Result<(), FlannError> {
+        LinearIndex::remove(self, idx)
+    }
+
+    fn search_nearest_neighbor(&self, point: &[T]) -> Result<Neighbor<T::ResultType>, FlannError> {
+        self.find_nearest_neighbor(point)
+    }
+
+    fn search_nearest_neighbors(
+        &self,
+        num: usize,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        Ok(self.find_nearest_neighbors(num, point)?.collect())
+    }
+
+    fn search_nearest_neighbors_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        Ok(self
+            .find_nearest_neighbors_radius(num, radius, point)?
+            .collect())
+    }
+
+    fn search_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.find_all_within_radius(radius, point)
+    }
+
+    fn search_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        self.find_many_nearest_neighbors_flat(num, points)
+    }
+}
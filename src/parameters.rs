@@ -1,6 +1,9 @@
-use enums::{Algorithm, CentersInit, Checks, LogLevel};
+use config::IndexConfig;
+use distance::Distance;
+use enums::{Algorithm, CentersInit, Checks, DistanceType, LogLevel};
 use raw;
 use std::os::raw::c_long;
+use FlannError;
 
 const DEFAULT_REBUILD_THRESHOLD: f32 = 2.0;
 
@@ -28,6 +31,10 @@ pub struct Parameters {
     pub log_level: LogLevel,
     pub random_seed: c_long,
     pub rebuild_threshold: f32,
+    /// The distance metric the index is built and searched with.
+    pub distance_type: DistanceType,
+    /// The order of `DistanceType::Minkowski`, ignored by other distances.
+    pub distance_order: i32,
 }
 
 impl Default for Parameters {
@@ -65,8 +72,17 @@ impl Parameters {
                 .ok_or_else(|| format!("Illegal log level enum value: {}", v.log_level))?,
             random_seed: v.random_seed,
             rebuild_threshold: DEFAULT_REBUILD_THRESHOLD,
+            distance_type: DistanceType::Euclidean,
+            distance_order: 0,
         })
     }
+
+    /// Checks that the distance is supported and that the fields used by
+    /// `algorithm` are in the ranges it accepts.
+    pub fn validate(&self) -> Result<(), FlannError> {
+        Distance::from_parameters(self)?;
+        IndexConfig::from_parameters(self).map_or(Ok(()), |config| config.validate())
+    }
 }
 
 impl<'a> Into<raw::FLANNParameters> for &'a Parameters {
@@ -102,3 +118,49 @@ impl Into<raw::FLANNParameters> for Parameters {
         (&self).into()
     }
 }
+
+/// Parameters that control a single search.
+///
+/// These override the search related fields of the `Parameters` an index
+/// was built with, so the same index can serve searches with different
+/// speed and accuracy tradeoffs.
+#[derive(Debug, Clone)]
+pub struct SearchParams {
+    pub checks: Checks,
+    pub eps: f32,
+    pub sorted: i32,
+    pub max_neighbors: i32,
+    pub cores: i32,
+}
+
+impl Default for SearchParams {
+    fn default() -> SearchParams {
+        (&Parameters::default()).into()
+    }
+}
+
+impl<'a> From<&'a Parameters> for SearchParams {
+    fn from(parameters: &'a Parameters) -> SearchParams {
+        SearchParams {
+            checks: parameters.checks,
+            eps: parameters.eps,
+            sorted: parameters.sorted,
+            max_neighbors: parameters.max_neighbors,
+            cores: parameters.cores,
+        }
+    }
+}
+
+impl SearchParams {
+    /// Returns a copy of `flann_params` with the search fields replaced.
+    pub(crate) fn apply(&self, flann_params: raw::FLANNParameters) -> raw::FLANNParameters {
+        raw::FLANNParameters {
+            checks: self.checks.as_raw(),
+            eps: self.eps,
+            sorted: self.sorted,
+            max_neighbors: self.max_neighbors,
+            cores: self.cores,
+            ..flann_params
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::iter::FromIterator;
+use FlannError;
+use Neighbor;
+
+/// The radii of a radius search over several points.
+#[derive(Copy, Clone, Debug)]
+pub enum Radii<'a> {
+    /// The same radius for every point.
+    Same(f32),
+    /// A radius for every point, in the same order as the points.
+    PerQuery(&'a [f32]),
+}
+
+impl<'a> Radii<'a> {
+    /// Checks that there is a radius for each of `num_queries` points.
+    pub(crate) fn check(&self, num_queries: usize) -> Result<(), FlannError> {
+        match *self {
+            Radii::PerQuery(radii) if radii.len() != num_queries => {
+                Err(FlannError::InvalidParameter {
+                    parameter: "radii",
+                    requirement: "must have one radius per point",
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The radius of the point `query`.
+    pub(crate) fn get(&self, query: usize) -> f32 {
+        match *self {
+            Radii::Same(radius) => radius,
+            Radii::PerQuery(radii) => radii[query],
+        }
+    }
+}
+
+impl From<f32> for Radii<'static> {
+    fn from(radius: f32) -> Self {
+        Radii::Same(radius)
+    }
+}
+
+impl<'a> From<&'a [f32]> for Radii<'a> {
+    fn from(radii: &'a [f32]) -> Self {
+        Radii::PerQuery(radii)
+    }
+}
+
+/// The neighbors within the radius of several points in compressed sparse
+/// row layout.
+///
+/// The neighbors of point `i` are at `offsets[i]..offsets[i + 1]` in
+/// `indices` and `distances`, sorted by closest to furthest.
+#[derive(Clone, Debug)]
+pub struct RadiusNeighbors<D> {
+    pub offsets: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub distances: Vec<D>,
+}
+
+impl<D> RadiusNeighbors<D> {
+    /// The number of points that were searched for.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the indices and distances of the neighbors of the point `query`.
+    pub fn get(&self, query: usize) -> Option<(&[usize], &[D])> {
+        if query < self.len() {
+            let range = self.offsets[query]..self.offsets[query + 1];
+            Some((&self.indices[range.clone()], &self.distances[range]))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the indices and distances of the neighbors of every point.
+    pub fn iter(&self) -> impl Iterator<Item = (&[usize], &[D])> {
+        (0..self.len()).map(move |query| self.get(query).unwrap())
+    }
+}
+
+impl<D> Default for RadiusNeighbors<D> {
+    fn default() -> Self {
+        Self {
+            offsets: vec![0],
+            indices: Vec::new(),
+            distances: Vec::new(),
+        }
+    }
+}
+
+impl<D> FromIterator<Vec<Neighbor<D>>> for RadiusNeighbors<D> {
+    fn from_iter<I: IntoIterator<Item = Vec<Neighbor<D>>>>(iter: I) -> Self {
+        let mut neighbors = Self::default();
+        for query_neighbors in iter {
+            for neighbor in query_neighbors {
+                neighbors.indices.push(neighbor.index);
+                neighbors.distances.push(neighbor.distance);
+            }
+            neighbors.offsets.push(neighbors.indices.len());
+        }
+        neighbors
+    }
+}
@@ -0,0 +1,125 @@
+use linear_index::{ExactDistance, LinearIndex};
+use nearest_neighbor_search::NearestNeighborSearch;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use FlannError;
+
+/// How long single searches took.
+#[derive(Debug, Clone)]
+pub struct LatencyStats {
+    pub mean: Duration,
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+/// How well an approximate index matches exact search.
+#[derive(Debug, Clone)]
+pub struct RecallReport {
+    /// The fraction of the true `k` nearest neighbors that were found,
+    /// averaged over the queries.
+    pub recall: f64,
+    /// The distance of the `i`-th neighbor found divided by the distance of
+    /// the `i`-th true neighbor, averaged over all neighbors with a nonzero
+    /// true distance. This is 1 for exact results.
+    pub mean_distance_ratio: f64,
+    /// The time the approximate index took per query.
+    pub latency: LatencyStats,
+}
+
+/// Measures the recall@k of `index` against the exact results of `ground_truth`.
+///
+/// Both indices must hold the same points and use the same distance. The
+/// queries must be in a slice of memory in component order where there are
+/// `point_len` components. Every query is searched on its own to measure
+/// its latency.
+pub fn evaluate_recall<T, I>(
+    index: &I,
+    ground_truth: &LinearIndex<T>,
+    queries: &[T],
+    k: usize,
+) -> Result<RecallReport, FlannError>
+where
+    T: ExactDistance,
+    I: NearestNeighborSearch<T> + ?Sized,
+{
+    if k == 0 {
+        return Err(FlannError::InvalidParameter {
+            parameter: "k",
+            requirement: "must be at least 1",
+        });
+    }
+    if queries.is_empty() {
+        return Err(FlannError::ZeroInputPoints);
+    }
+    // Otherwise there would be no exact neighbors to compare with.
+    if ground_truth.is_empty() {
+        return Err(FlannError::InvalidParameter {
+            parameter: "ground_truth",
+            requirement: "must have at least one point that wasn't removed",
+        });
+    }
+    let point_len = index.point_len();
+    if queries.len() % point_len != 0 {
+        return Err(FlannError::InvalidFlatPointsLen {
+            expected: point_len,
+            got: queries.len(),
+        });
+    }
+
+    let mut recall = 0.0;
+    let mut distance_ratio = 0.0;
+    let mut num_ratios = 0usize;
+    let mut latencies = Vec::with_capacity(queries.len() / point_len);
+    for query in queries.chunks(point_len) {
+        let start = Instant::now();
+        let found = index.search_nearest_neighbors(k, query)?;
+        latencies.push(start.elapsed());
+        let exact = ground_truth
+            .find_nearest_neighbors(k, query)?
+            .collect::<Vec<_>>();
+
+        let exact_indices = exact
+            .iter()
+            .map(|neighbor| neighbor.index)
+            .collect::<HashSet<_>>();
+        let hits = found
+            .iter()
+            .filter(|neighbor| exact_indices.contains(&neighbor.index))
+            .count();
+        recall += hits as f64 / exact.len() as f64;
+
+        for (found, exact) in found.iter().zip(&exact) {
+            let exact = T::distance_to_f64(&exact.distance);
+            if exact > 0.0 {
+                let found = T::distance_to_f64(&found.distance);
+                distance_ratio += found / exact;
+                num_ratios += 1;
+            }
+        }
+    }
+
+    let num_queries = latencies.len();
+    Ok(RecallReport {
+        recall: recall / num_queries as f64,
+        mean_distance_ratio: if num_ratios == 0 {
+            1.0
+        } else {
+            distance_ratio / num_ratios as f64
+        },
+        latency: latency_stats(latencies),
+    })
+}
+
+fn latency_stats(mut latencies: Vec<Duration>) -> LatencyStats {
+    latencies.sort();
+    let percentile = |p: f64| latencies[((latencies.len() - 1) as f64 * p).round() as usize];
+    LatencyStats {
+        mean: latencies.iter().sum::<Duration>() / latencies.len() as u32,
+        min: latencies[0],
+        median: percentile(0.5),
+        p95: percentile(0.95),
+        max: latencies[latencies.len() - 1],
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::hash_map::RandomState;
+use std::convert::TryInto;
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read};
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use Algorithm;
+use FlannError;
+use Parameters;
+
+/// The algorithm of a saved FLANN index and the shape of the dataset that it
+/// was built from.
+pub(crate) struct SavedIndexShape {
+    pub algorithm: Algorithm,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl SavedIndexShape {
+    /// Checks that a flat dataset of `points_len` components split into
+    /// points of `point_len` components has the shape FLANN expects.
+    pub fn check(&self, point_len: usize, points_len: usize) -> Result<(), FlannError> {
+        if point_len != self.cols {
+            return Err(FlannError::InvalidPointDimensionality {
+                expected: self.cols,
+                got: point_len,
+            });
+        }
+        if points_len / point_len != self.rows {
+            return Err(FlannError::InvalidPointCount {
+                expected: self.rows,
+                got: points_len / point_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that `parameters` are valid for the saved index.
+    ///
+    /// `Autotuned` and `Saved` parameters take the algorithm of the saved
+    /// index, which is the one FLANN chose for autotuned indices. Any other
+    /// algorithm must be the one the index was saved with.
+    pub fn check_parameters(&self, parameters: &mut Parameters) -> Result<(), FlannError> {
+        match parameters.algorithm {
+            Algorithm::Autotuned | Algorithm::Saved => parameters.algorithm = self.algorithm,
+            algorithm if algorithm.as_raw() != self.algorithm.as_raw() => {
+                return Err(FlannError::InvalidAlgorithm { algorithm });
+            }
+            _ => {}
+        }
+        parameters.validate()
+    }
+}
+
+pub(crate) fn path_to_cstring<P: AsRef<Path>>(path: P) -> Result<CString, FlannError> {
+    path.as_ref()
+        .to_str()
+        .and_then(|s| CString::new(s).ok())
+        .ok_or(FlannError::InvalidPath)
+}
+
+/// Reads the dataset shape from the header FLANN writes at the start of a saved index.
+pub(crate) fn read_shape<P: AsRef<Path>>(path: P) -> Result<SavedIndexShape, FlannError> {
+    let mut header = [0u8; 64];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(|_| FlannError::FailedToLoadIndex)?;
+    // FLANN 1.9 widened the signature field from 16 to 24 bytes.
+    let signature_len = if header.starts_with(b"FLANN_INDEX_v1.1") {
+        24
+    } else if header.starts_with(b"FLANN_INDEX") {
+        16
+    } else {
+        return Err(FlannError::InvalidSavedIndex);
+    };
+    // The signature is followed by a 16 byte version string and two 4 byte
+    // enums, the data type and the algorithm.
+    let algorithm_offset = signature_len + 16 + 4;
+    let algorithm = u32::from_ne_bytes(
+        header[algorithm_offset..algorithm_offset + 4]
+            .try_into()
+            .unwrap(),
+    );
+    let algorithm = Algorithm::from_raw(algorithm).ok_or(FlannError::InvalidSavedIndex)?;
+    let rows_offset = algorithm_offset + 4;
+    let read_size = |offset: usize| {
+        usize::from_ne_bytes(
+            header[offset..offset + size_of::<usize>()]
+                .try_into()
+                .unwrap(),
+        )
+    };
+    Ok(SavedIndexShape {
+        algorithm,
+        rows: read_size(rows_offset),
+        cols: read_size(rows_offset + size_of::<usize>()),
+    })
+}
+
+/// A uniquely named file in the temporary directory that is removed on drop.
+///
+/// FLANN can only save and load indices by path, so this is used to stage
+/// index data that is embedded in other files.
+pub(crate) struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Creates a new empty file with a random name, failing instead of
+    /// following anything that already exists at that path.
+    pub fn new() -> io::Result<(Self, File)> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u32(std::process::id());
+            hasher.write_usize(COUNTER.fetch_add(1, Ordering::Relaxed));
+            let name = format!("flann-{:016x}.idx", hasher.finish());
+            let path = std::env::temp_dir().join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((Self { path }, file)),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
@@ -0,0 +1,76 @@
+/// Memory for the results of k-NN searches that is reused between searches.
+///
+/// See `SliceIndex::knn_search_into_buffers`.
+#[derive(Clone, Debug)]
+pub struct SearchBuffers<D> {
+    pub(crate) indices: Vec<usize>,
+    pub(crate) distances: Vec<D>,
+    num_queries: usize,
+    num_neighbors: usize,
+}
+
+impl<D: Clone + Default> SearchBuffers<D> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Makes buffers with room for `capacity` neighbors in total.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            indices: Vec::with_capacity(capacity),
+            distances: Vec::with_capacity(capacity),
+            num_queries: 0,
+            num_neighbors: 0,
+        }
+    }
+
+    /// Makes room for `len` neighbors, only allocating if the buffers are too small.
+    pub(crate) fn resize(&mut self, len: usize) {
+        self.indices.resize(len, 0);
+        self.distances.resize(len, D::default());
+        self.num_queries = 0;
+        self.num_neighbors = 0;
+    }
+
+    pub(crate) fn set_shape(&mut self, num_queries: usize, num_neighbors: usize) {
+        self.num_queries = num_queries;
+        self.num_neighbors = num_neighbors;
+    }
+
+    /// The number of points of the last search.
+    pub fn len(&self) -> usize {
+        self.num_queries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_queries == 0
+    }
+
+    /// The number of neighbors that were found for every point in the last search.
+    pub fn num_neighbors(&self) -> usize {
+        self.num_neighbors
+    }
+
+    /// Get the indices and distances of the neighbors of the point `query`
+    /// of the last search.
+    pub fn get(&self, query: usize) -> Option<(&[usize], &[D])> {
+        if query < self.num_queries {
+            let range = query * self.num_neighbors..(query + 1) * self.num_neighbors;
+            Some((&self.indices[range.clone()], &self.distances[range]))
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the indices and distances of the neighbors of every
+    /// point of the last search.
+    pub fn iter(&self) -> impl Iterator<Item = (&[usize], &[D])> {
+        (0..self.num_queries).map(move |query| self.get(query).unwrap())
+    }
+}
+
+impl<D: Clone + Default> Default for SearchBuffers<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
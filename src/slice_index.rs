@@ -1,23 +1,58 @@
-use itertools::{IntoChunks, Itertools};
+use build_report::BuildReport;
+use distance::Distance;
+use knn_results::KnnResults;
+use radius_neighbors::{Radii, RadiusNeighbors};
 use raw;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use saved_index;
+use search_buffers::SearchBuffers;
+use std::path::Path;
+use std::time::Instant;
+use vec_index::VecIndex;
 use FlannError;
 use Indexable;
 use Neighbor;
 use Parameters;
+use RadiusResult;
+use SearchParams;
+
+/// The number of points each rayon task searches at once.
+#[cfg(feature = "rayon")]
+pub(crate) const PARALLEL_CHUNK_LEN: usize = 64;
+
+/// The number of neighbors that radius searches for all neighbors have room
+/// for at first.
+pub(crate) const MIN_RADIUS_BUFFER_LEN: usize = 32;
 
 pub struct SliceIndex<'a, T: Indexable> {
     index: raw::flann_index_t,
     parameters: raw::FLANNParameters,
     rebuild_threshold: f32,
+    distance: Distance,
+    build_report: Option<BuildReport>,
     pub(crate) point_len: usize,
+    /// Points the index copied instead of borrowing, which FLANN refers to.
+    _owned_points: Vec<T>,
+    /// Whether the point with every id that was ever added was removed.
+    removed: Vec<bool>,
+    num_removed: usize,
     _phantom: std::marker::PhantomData<&'a T>,
 }
 
+// FLANN indices are not tied to the thread that created them. Searches only read
+// from the index and each one passes FLANN its own copy of the parameters, while
+// everything that modifies the index requires `&mut self`. The index borrows
+// points like `&'a [T]` and may own some like `Vec<T>`, so it needs both
+// `T: Send` and `T: Sync` to be sent.
+unsafe impl<'a, T: Indexable + Send + Sync> Send for SliceIndex<'a, T> {}
+unsafe impl<'a, T: Indexable + Sync> Sync for SliceIndex<'a, T> {}
+
 impl<'a, T: Indexable> Drop for SliceIndex<'a, T> {
     fn drop(&mut self) {
-        unsafe {
+        self.distance.with(|| unsafe {
             T::free_index(self.index, &mut self.parameters);
-        }
+        })
     }
 }
 
@@ -40,11 +75,15 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
                 got: points.len(),
             });
         }
-        // This stores how much faster FLANN executed compared to linear, which we discard.
+        parameters.validate()?;
+        // This stores how much faster FLANN executed compared to linear when autotuning.
         let mut speedup = 0.0;
+        let distance = Distance::from_parameters(&parameters)?;
         let rebuild_threshold = parameters.rebuild_threshold;
+        let requested = parameters.algorithm;
         let mut flann_params = parameters.into();
-        let index = unsafe {
+        let start = Instant::now();
+        let index = distance.with(|| unsafe {
             T::build_index(
                 points.as_ptr() as *mut T,
                 (points.len() / point_len) as i32,
@@ -52,19 +91,100 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
                 &mut speedup,
                 &mut flann_params,
             )
-        };
+        });
+        let duration = start.elapsed();
         if index.is_null() {
             return Err(FlannError::FailedToBuildIndex);
         }
-        Ok(Self {
+        let mut index = Self {
             index,
             parameters: flann_params,
             rebuild_threshold,
+            distance,
+            build_report: None,
+            point_len,
+            _owned_points: Vec::new(),
+            removed: vec![false; points.len() / point_len],
+            num_removed: 0,
+            _phantom: Default::default(),
+        };
+        index.build_report = Some(BuildReport::new(
+            requested,
+            speedup,
+            index.parameters(),
+            duration,
+        ));
+        Ok(index)
+    }
+
+    /// Loads an index previously written with `save` for the points that are
+    /// already in a slice of memory in component order where there are
+    /// `point_len` components.
+    ///
+    /// The points must be the same ones the index was saved with, since FLANN
+    /// does not store the dataset in the index file.
+    ///
+    /// This borrows the slice internally in FLANN.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        point_len: usize,
+        points: &'a [T],
+        mut parameters: Parameters,
+    ) -> Result<Self, FlannError> {
+        if points.is_empty() {
+            return Err(FlannError::ZeroInputPoints);
+        }
+        if points.len() % point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: point_len,
+                got: points.len(),
+            });
+        }
+        let shape = saved_index::read_shape(&path)?;
+        shape.check(point_len, points.len())?;
+        shape.check_parameters(&mut parameters)?;
+        let filename = saved_index::path_to_cstring(&path)?;
+        let distance = Distance::from_parameters(&parameters)?;
+        let rebuild_threshold = parameters.rebuild_threshold;
+        let index = distance.with(|| unsafe {
+            T::load_index(
+                filename.as_ptr() as *mut _,
+                points.as_ptr() as *mut T,
+                (points.len() / point_len) as i32,
+                point_len as i32,
+            )
+        });
+        if index.is_null() {
+            return Err(FlannError::FailedToLoadIndex);
+        }
+        Ok(Self {
+            index,
+            parameters: parameters.into(),
+            rebuild_threshold,
+            distance,
+            build_report: None,
             point_len,
+            _owned_points: Vec::new(),
+            removed: vec![false; points.len() / point_len],
+            num_removed: 0,
             _phantom: Default::default(),
         })
     }
 
+    /// Saves the index to a file so that it can be restored with `load`.
+    ///
+    /// The points are not saved, so they must be kept separately.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FlannError> {
+        let filename = saved_index::path_to_cstring(path)?;
+        let retval = self
+            .distance
+            .with(|| unsafe { T::save_index(self.index, filename.as_ptr() as *mut _) });
+        if retval != 0 {
+            return Err(FlannError::FailedToSaveIndex);
+        }
+        Ok(())
+    }
+
     /// Adds a point to the index.
     pub fn add_slice(&mut self, point: &'a [T]) -> Result<(), FlannError> {
         if point.len() != self.point_len {
@@ -73,7 +193,7 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
                 got: point.len(),
             });
         }
-        let retval = unsafe {
+        let retval = self.distance.with(|| unsafe {
             T::add_points(
                 self.index,
                 point.as_ptr() as *mut T,
@@ -81,8 +201,11 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
                 self.point_len as i32,
                 self.rebuild_threshold,
             )
-        };
-        assert_eq!(retval, 0);
+        });
+        if retval != 0 {
+            return Err(FlannError::AddPointsFailed);
+        }
+        self.removed.push(false);
         Ok(())
     }
 
@@ -98,7 +221,7 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
                 got: points.len(),
             });
         }
-        let retval = unsafe {
+        let retval = self.distance.with(|| unsafe {
             T::add_points(
                 self.index,
                 points.as_ptr() as *mut T,
@@ -106,16 +229,26 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
                 self.point_len as i32,
                 self.rebuild_threshold,
             )
-        };
-        assert_eq!(retval, 0);
+        });
+        if retval != 0 {
+            return Err(FlannError::AddPointsFailed);
+        }
+        let len = self.removed.len() + points.len() / self.point_len;
+        self.removed.resize(len, false);
         Ok(())
     }
 
     /// Get the point that corresponds to this index `idx`.
-    pub fn get(&self, idx: usize) -> Option<&'a [T]> {
-        if idx < self.len() {
-            let point = unsafe { T::get_point(self.index, idx as u32) };
-            assert!(!point.is_null());
+    ///
+    /// Returns `None` if there is no such point or it was removed.
+    pub fn get(&self, idx: usize) -> Option<&[T]> {
+        if self.removed.get(idx) == Some(&false) {
+            let point = self
+                .distance
+                .with(|| unsafe { T::get_point(self.index, idx as u32) });
+            if point.is_null() {
+                return None;
+            }
             Some(unsafe { std::slice::from_raw_parts(point, self.point_len) })
         } else {
             None
@@ -123,13 +256,107 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
     }
 
     /// Removes a point at index `idx`.
-    pub fn remove(&mut self, idx: usize) {
-        let retval = unsafe { T::remove_point(self.index, idx as u32) };
-        assert_eq!(retval, 0);
+    pub fn remove(&mut self, idx: usize) -> Result<(), FlannError> {
+        let retval = self
+            .distance
+            .with(|| unsafe { T::remove_point(self.index, idx as u32) });
+        if retval != 0 {
+            return Err(FlannError::RemoveFailed { idx });
+        }
+        if let Some(removed) = self.removed.get_mut(idx) {
+            if !*removed {
+                *removed = true;
+                self.num_removed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of points that weren't removed.
+    pub fn live_len(&self) -> usize {
+        self.removed.len() - self.num_removed
+    }
+
+    /// The number of removed points whose ids are still taken, until the
+    /// index is compacted.
+    pub fn removed_len(&self) -> usize {
+        self.num_removed
+    }
+
+    /// The ids of the removed points.
+    pub(crate) fn removed_ids(&self) -> Vec<usize> {
+        (0..self.removed.len())
+            .filter(|&idx| self.removed[idx])
+            .collect()
+    }
+
+    /// Iterates over the ids and components of the points that weren't removed.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &[T])> + '_ {
+        (0..self.removed.len()).filter_map(move |idx| self.get(idx).map(|point| (idx, point)))
+    }
+
+    /// Makes a new index that owns points that are in a `Vec` in component
+    /// order where there are `point_len` components.
+    #[cfg(feature = "ndarray")]
+    pub(crate) fn from_owned(
+        point_len: usize,
+        points: Vec<T>,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError> {
+        // The heap allocation of `points` doesn't move when it is moved into the
+        // index, and `drop` frees FLANN's index before the fields are dropped.
+        // Points are only handed out borrowed from `&self`.
+        let slice = unsafe { std::slice::from_raw_parts(points.as_ptr(), points.len()) };
+        let mut index = Self::new(point_len, slice, parameters)?;
+        index._owned_points = points;
+        Ok(index)
+    }
+
+    /// Builds a new index from copies of the points that weren't removed with
+    /// the parameters that the index uses.
+    ///
+    /// Returns the new index and the new id of every old id, or `None` for
+    /// removed points.
+    pub fn compact(&self) -> Result<(VecIndex<T>, Vec<Option<usize>>), FlannError>
+    where
+        T: 'static,
+    {
+        let (points, remap) = self.live_points();
+        let index = VecIndex::from_flat(self.point_len, points, self.parameters())?;
+        Ok((index, remap))
+    }
+
+    /// Copies the points that weren't removed into the flat layout and maps
+    /// their old ids to their positions.
+    pub(crate) fn live_points(&self) -> (Vec<T>, Vec<Option<usize>>) {
+        let mut points = Vec::with_capacity(self.live_len() * self.point_len);
+        let mut remap = vec![None; self.removed.len()];
+        for (new_idx, (idx, point)) in self.iter().enumerate() {
+            points.extend_from_slice(point);
+            remap[idx] = Some(new_idx);
+        }
+        (points, remap)
+    }
+
+    /// Returns the parameters that the index uses.
+    pub fn parameters(&self) -> Parameters {
+        let mut parameters =
+            Parameters::from_raw(self.parameters).expect("Illegal FLANN parameters in index");
+        parameters.rebuild_threshold = self.rebuild_threshold;
+        parameters.distance_type = self.distance.distance_type;
+        parameters.distance_order = self.distance.order;
+        parameters
+    }
+
+    /// Returns what FLANN reported when building the index, or `None` if
+    /// the index was loaded.
+    pub fn build_report(&self) -> Option<&BuildReport> {
+        self.build_report.as_ref()
     }
 
     pub fn len(&self) -> usize {
-        unsafe { T::size(self.index) as usize }
+        self.distance
+            .with(|| unsafe { T::size(self.index) as usize })
     }
 
     pub fn is_empty(&self) -> bool {
@@ -138,7 +365,7 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
 
     /// Performs a search to find only the closest neighbor.
     pub fn find_nearest_neighbor(
-        &mut self,
+        &self,
         point: &[T],
     ) -> Result<Neighbor<T::ResultType>, FlannError> {
         if point.len() != self.point_len {
@@ -148,31 +375,56 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
             });
         }
         let mut index = -1;
-        let mut distance_squared = T::ResultType::default();
-        let retval = unsafe {
+        let mut distance = T::ResultType::default();
+        let mut flann_params = self.parameters;
+        let retval = self.distance.with(|| unsafe {
             T::find_nearest_neighbors_index(
                 self.index,
                 point.as_ptr() as *mut T,
                 1,
                 &mut index,
-                &mut distance_squared,
+                &mut distance,
                 1,
-                &mut self.parameters,
+                &mut flann_params,
             )
-        };
-        assert_eq!(retval, 0);
+        });
+        if retval != 0 {
+            return Err(FlannError::SearchFailed);
+        }
         Ok(Neighbor {
             index: index as usize,
-            distance_squared,
+            distance,
         })
     }
 
     /// Performs k-NN search for `num` neighbors.
     /// If there are less points in the set than `num` it returns that many neighbors.
     pub fn find_nearest_neighbors(
-        &mut self,
+        &self,
         num: usize,
         point: &[T],
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_nearest_neighbors_raw(num, point, flann_params)
+    }
+
+    /// Performs k-NN search for `num` neighbors using `params` instead of
+    /// the search parameters the index was built with.
+    pub fn find_nearest_neighbors_with_params(
+        &self,
+        num: usize,
+        point: &[T],
+        params: &SearchParams,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_nearest_neighbors_raw(num, point, flann_params)
+    }
+
+    fn find_nearest_neighbors_raw(
+        &self,
+        num: usize,
+        point: &[T],
+        mut flann_params: raw::FLANNParameters,
     ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
         if point.len() != self.point_len {
             return Err(FlannError::InvalidPointDimensionality {
@@ -182,25 +434,28 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
         }
         let num = num.min(self.len());
         let mut indices: Vec<i32> = vec![-1; num];
-        let mut distances_squared: Vec<T::ResultType> = vec![T::ResultType::default(); num];
-        let retval = unsafe {
+        let mut distances: Vec<T::ResultType> = vec![T::ResultType::default(); num];
+        let retval = self.distance.with(|| unsafe {
             T::find_nearest_neighbors_index(
                 self.index,
                 point.as_ptr() as *mut T,
                 1,
                 indices.as_mut_ptr(),
-                distances_squared.as_mut_ptr(),
+                distances.as_mut_ptr(),
                 num as i32,
-                &mut self.parameters,
+                &mut flann_params,
             )
-        };
-        assert_eq!(retval, 0);
-        Ok(indices.into_iter().zip(distances_squared.into_iter()).map(
-            |(index, distance_squared)| Neighbor {
+        });
+        if retval != 0 {
+            return Err(FlannError::SearchFailed);
+        }
+        Ok(indices
+            .into_iter()
+            .zip(distances.into_iter())
+            .map(|(index, distance)| Neighbor {
                 index: index as usize,
-                distance_squared,
-            },
-        ))
+                distance,
+            }))
     }
 
     /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance.
@@ -208,40 +463,318 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
     ///
     /// The returned iterator is sorted by closest to furthest.
     pub fn find_nearest_neighbors_radius(
-        &mut self,
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_nearest_neighbors_radius_raw(num, radius, point, flann_params)
+    }
+
+    /// Performs k-NN search for `num` neighbors, limiting the search to `radius` distance,
+    /// using `params` instead of the search parameters the index was built with.
+    pub fn find_nearest_neighbors_radius_with_params(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+        params: &SearchParams,
+    ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_nearest_neighbors_radius_raw(num, radius, point, flann_params)
+    }
+
+    fn find_nearest_neighbors_radius_raw(
+        &self,
         num: usize,
-        radius_squared: f32,
+        radius: f32,
         point: &[T],
+        flann_params: raw::FLANNParameters,
     ) -> Result<impl Iterator<Item = Neighbor<T::ResultType>>, FlannError> {
+        let neighbors = self.radius_search_raw(num.min(self.len()), radius, point, flann_params)?;
+        Ok(neighbors.into_iter())
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// and reports whether there were more.
+    pub fn find_within_radius(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_within_radius_raw(num, radius, point, flann_params)
+    }
+
+    /// Performs a search for at most `num` neighbors within `radius` distance
+    /// using `params` instead of the search parameters the index was built with.
+    pub fn find_within_radius_with_params(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+        params: &SearchParams,
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_within_radius_raw(num, radius, point, flann_params)
+    }
+
+    fn find_within_radius_raw(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+        flann_params: raw::FLANNParameters,
+    ) -> Result<RadiusResult<T::ResultType>, FlannError> {
+        // One more neighbor than requested is searched for to tell if there are more.
+        let mut neighbors =
+            self.radius_search_raw(num.min(self.len()) + 1, radius, point, flann_params)?;
+        let truncated = neighbors.len() > num;
+        neighbors.truncate(num);
+        Ok(RadiusResult {
+            neighbors,
+            truncated,
+        })
+    }
+
+    /// Performs a search for all neighbors within `radius` distance.
+    ///
+    /// The neighbors are sorted by closest to furthest.
+    pub fn find_all_within_radius(
+        &self,
+        radius: f32,
+        point: &[T],
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_all_within_radius_raw(radius, point, flann_params)
+    }
+
+    /// Performs a search for all neighbors within `radius` distance using
+    /// `params` instead of the search parameters the index was built with.
+    pub fn find_all_within_radius_with_params(
+        &self,
+        radius: f32,
+        point: &[T],
+        params: &SearchParams,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_all_within_radius_raw(radius, point, flann_params)
+    }
+
+    fn find_all_within_radius_raw(
+        &self,
+        radius: f32,
+        point: &[T],
+        flann_params: raw::FLANNParameters,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        self.radius_search_all_into(
+            radius,
+            point,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            flann_params,
+        )
+    }
+
+    /// Performs a search for all neighbors within the radius of several points.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    pub fn find_many_within_radius<'r, R, I, P>(
+        &self,
+        radii: R,
+        points: I,
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError>
+    where
+        R: Into<Radii<'r>>,
+        I: IntoIterator<Item = P>,
+        P: IntoIterator<Item = T>,
+    {
+        let mut points_vec = Vec::new();
+        for point in points {
+            let count = point.into_iter().map(|d| points_vec.push(d)).count();
+            if count != self.point_len {
+                return Err(FlannError::InvalidPointDimensionality {
+                    expected: self.point_len,
+                    got: count,
+                });
+            }
+        }
+        self.find_many_within_radius_flat(radii, &points_vec)
+    }
+
+    /// Performs a search for all neighbors within the radius of several points.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    pub fn find_many_within_radius_flat<'r, R: Into<Radii<'r>>>(
+        &self,
+        radii: R,
+        points: &[T],
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError> {
+        let radii = radii.into();
+        self.check_radius_queries(&radii, points)?;
+        let (mut indices, mut distances) = (Vec::new(), Vec::new());
+        points
+            .chunks(self.point_len)
+            .enumerate()
+            .map(|(query, point)| {
+                self.radius_search_all_into(
+                    radii.get(query),
+                    point,
+                    &mut indices,
+                    &mut distances,
+                    self.parameters,
+                )
+            })
+            .collect()
+    }
+
+    /// Performs a search for all neighbors within the radius of several points
+    /// on the rayon thread pool.
+    ///
+    /// `radii` is either one radius for all points or a slice with the radius of every point.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    #[cfg(feature = "rayon")]
+    pub fn par_find_many_within_radius_flat<'r, R: Into<Radii<'r>>>(
+        &self,
+        radii: R,
+        points: &[T],
+    ) -> Result<RadiusNeighbors<T::ResultType>, FlannError>
+    where
+        T: Sync,
+        T::ResultType: Send,
+    {
+        let radii = radii.into();
+        self.check_radius_queries(&radii, points)?;
+        let neighbors = points
+            .par_chunks(self.point_len)
+            .enumerate()
+            .map_init(
+                || (Vec::new(), Vec::new()),
+                |(indices, distances), (query, point)| {
+                    self.radius_search_all_into(
+                        radii.get(query),
+                        point,
+                        indices,
+                        distances,
+                        self.parameters,
+                    )
+                },
+            )
+            .collect::<Result<Vec<_>, FlannError>>()?;
+        Ok(neighbors.into_iter().collect())
+    }
+
+    fn check_radius_queries(&self, radii: &Radii, points: &[T]) -> Result<(), FlannError> {
+        if points.len() % self.point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: self.point_len,
+                got: points.len(),
+            });
+        }
+        radii.check(points.len() / self.point_len)
+    }
+
+    /// Searches for all neighbors within `radius` distance, reusing `indices`
+    /// and `distances` between searches.
+    ///
+    /// The buffers start small and double whenever a search fills them, so
+    /// dense queries are searched again but sparse ones stay cheap.
+    fn radius_search_all_into(
+        &self,
+        radius: f32,
+        point: &[T],
+        indices: &mut Vec<i32>,
+        distances: &mut Vec<T::ResultType>,
+        flann_params: raw::FLANNParameters,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        // No more neighbors than there are points can be found.
+        let max_len = self.len();
+        if max_len == 0 {
+            return Ok(Vec::new());
+        }
+        let mut len = indices.len().max(MIN_RADIUS_BUFFER_LEN).min(max_len);
+        loop {
+            indices.resize(len, -1);
+            distances.resize(len, T::ResultType::default());
+            let found =
+                self.radius_search_into_raw(radius, point, indices, distances, flann_params)?;
+            if found < len || len == max_len {
+                return Ok(indices[..found]
+                    .iter()
+                    .zip(&distances[..found])
+                    .map(|(&index, distance)| Neighbor {
+                        index: index as usize,
+                        distance: distance.clone(),
+                    })
+                    .collect());
+            }
+            len = len.saturating_mul(2).min(max_len);
+        }
+    }
+
+    /// Searches for at most `num` neighbors within `radius` distance.
+    fn radius_search_raw(
+        &self,
+        num: usize,
+        radius: f32,
+        point: &[T],
+        flann_params: raw::FLANNParameters,
+    ) -> Result<Vec<Neighbor<T::ResultType>>, FlannError> {
+        let mut indices: Vec<i32> = vec![-1; num];
+        let mut distances: Vec<T::ResultType> = vec![T::ResultType::default(); num];
+        let found =
+            self.radius_search_into_raw(radius, point, &mut indices, &mut distances, flann_params)?;
+        Ok(indices
+            .into_iter()
+            .zip(distances.into_iter())
+            .take(found)
+            .map(|(index, distance)| Neighbor {
+                index: index as usize,
+                distance,
+            })
+            .collect())
+    }
+
+    /// Searches for as many neighbors within `radius` distance as fit into
+    /// `indices` and `distances` and returns how many were found.
+    ///
+    /// If the buffers are empty, FLANN counts all neighbors within `radius`.
+    fn radius_search_into_raw(
+        &self,
+        radius: f32,
+        point: &[T],
+        indices: &mut [i32],
+        distances: &mut [T::ResultType],
+        mut flann_params: raw::FLANNParameters,
+    ) -> Result<usize, FlannError> {
         if point.len() != self.point_len {
             return Err(FlannError::InvalidPointDimensionality {
                 expected: self.point_len,
                 got: point.len(),
             });
         }
-        let num = num.min(self.len());
-        let mut indices: Vec<i32> = vec![-1; num];
-        let mut distances_squared: Vec<T::ResultType> = vec![T::ResultType::default(); num];
-        let retval = unsafe {
+        let retval = self.distance.with(|| unsafe {
             T::radius_search(
                 self.index,
                 point.as_ptr() as *mut T,
                 indices.as_mut_ptr(),
-                distances_squared.as_mut_ptr(),
-                num as i32,
-                radius_squared,
-                &mut self.parameters,
+                distances.as_mut_ptr(),
+                indices.len().min(distances.len()) as i32,
+                radius,
+                &mut flann_params,
             )
-        };
-        assert!(retval >= 0);
-        Ok(indices
-            .into_iter()
-            .zip(distances_squared.into_iter())
-            .take(retval as usize)
-            .map(|(index, distance_squared)| Neighbor {
-                index: index as usize,
-                distance_squared,
-            }))
+        });
+        if retval < 0 {
+            return Err(FlannError::SearchFailed);
+        }
+        Ok(retval as usize)
     }
 
     /// Performs k-NN search for `num` neighbors for several points.
@@ -249,10 +782,10 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
     /// If there are less points in the set than `num` it returns that many
     /// neighbors for each point.
     pub fn find_many_nearest_neighbors<I, P>(
-        &mut self,
+        &self,
         num: usize,
         points: I,
-    ) -> Result<IntoChunks<impl Iterator<Item = Neighbor<T::ResultType>>>, FlannError>
+    ) -> Result<KnnResults<T::ResultType>, FlannError>
     where
         I: IntoIterator<Item = P>,
         P: IntoIterator<Item = T>,
@@ -279,50 +812,185 @@ impl<'a, T: Indexable> SliceIndex<'a, T> {
     /// in component order where there are `point_len` components
     /// (as specified in `new` or `new_flat`).
     pub fn find_many_nearest_neighbors_flat(
-        &mut self,
+        &self,
         num: usize,
         points: &[T],
-    ) -> Result<IntoChunks<impl Iterator<Item = Neighbor<T::ResultType>>>, FlannError> {
-        let neighbor_from_index_distance = |(index, distance_squared)| Neighbor {
-            index: index as usize,
-            distance_squared,
-        };
-        if points.is_empty() {
-            let indices: Vec<i32> = Vec::new();
-            let distances: Vec<T::ResultType> = Vec::new();
-            return Ok(indices
-                .into_iter()
-                .zip(distances.into_iter())
-                .map(neighbor_from_index_distance)
-                .chunks(num));
-        }
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        let flann_params = self.parameters;
+        self.find_many_nearest_neighbors_flat_raw(num, points, flann_params)
+    }
+
+    /// Performs k-NN search on `num` neighbors for several points using `params`
+    /// instead of the search parameters the index was built with.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    pub fn find_many_nearest_neighbors_flat_with_params(
+        &self,
+        num: usize,
+        points: &[T],
+        params: &SearchParams,
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        let flann_params = params.apply(self.parameters);
+        self.find_many_nearest_neighbors_flat_raw(num, points, flann_params)
+    }
+
+    /// Performs k-NN search for `k` neighbors for several points without allocating.
+    ///
+    /// `indices` and `distances` must have room for `k` neighbors of every point.
+    /// If there are less points in the set than `k` it finds that many neighbors
+    /// instead, so this returns how many neighbors were found for every point. The
+    /// neighbors of point `i` are then at `i * found..(i + 1) * found` in the buffers.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    pub fn knn_search_into(
+        &self,
+        points: &[T],
+        k: usize,
+        indices: &mut [usize],
+        distances: &mut [T::ResultType],
+    ) -> Result<usize, FlannError> {
+        let flann_params = self.parameters;
+        self.knn_search_into_raw(points, k, indices, distances, flann_params)
+    }
+
+    fn knn_search_into_raw(
+        &self,
+        points: &[T],
+        k: usize,
+        indices: &mut [usize],
+        distances: &mut [T::ResultType],
+        mut flann_params: raw::FLANNParameters,
+    ) -> Result<usize, FlannError> {
         if points.len() % self.point_len != 0 {
             return Err(FlannError::InvalidFlatPointsLen {
                 expected: self.point_len,
                 got: points.len(),
             });
         }
-        let num = num.min(self.len());
         let total_points = points.len() / self.point_len;
-        let mut indices: Vec<i32> = vec![-1; num * total_points];
-        let mut distances_squared: Vec<T::ResultType> =
-            vec![T::ResultType::default(); num * total_points];
-        let retval = unsafe {
+        let capacity = total_points.saturating_mul(k);
+        if indices.len() < capacity {
+            return Err(FlannError::InvalidParameter {
+                parameter: "indices",
+                requirement: "must have room for k neighbors of every point",
+            });
+        }
+        if distances.len() < capacity {
+            return Err(FlannError::InvalidParameter {
+                parameter: "distances",
+                requirement: "must have room for k neighbors of every point",
+            });
+        }
+        let k = k.min(self.len());
+        if k == 0 || total_points == 0 {
+            return Ok(k);
+        }
+        // FLANN writes `int` indices, so they are written to the start of `indices`
+        // and widened afterwards. `usize` is at least as wide as `int` on every
+        // platform FLANN supports.
+        let found = total_points * k;
+        let raw_indices = indices.as_mut_ptr();
+        let retval = self.distance.with(|| unsafe {
             T::find_nearest_neighbors_index(
                 self.index,
                 points.as_ptr() as *mut T,
                 total_points as i32,
-                indices.as_mut_ptr(),
-                distances_squared.as_mut_ptr(),
-                num as i32,
-                &mut self.parameters,
+                raw_indices as *mut i32,
+                distances.as_mut_ptr(),
+                k as i32,
+                &mut flann_params,
             )
-        };
-        assert_eq!(retval, 0);
-        Ok(indices
-            .into_iter()
-            .zip(distances_squared.into_iter())
-            .map(neighbor_from_index_distance)
-            .chunks(num))
+        });
+        if retval != 0 {
+            return Err(FlannError::SearchFailed);
+        }
+        // Widening from the back only overwrites `int`s that were already widened.
+        for idx in (0..found).rev() {
+            unsafe {
+                let index = (raw_indices as *const i32).add(idx).read();
+                raw_indices.add(idx).write(index as usize);
+            }
+        }
+        Ok(k)
+    }
+
+    /// Performs k-NN search for `k` neighbors for several points, reusing the
+    /// memory of `buffers` from previous searches.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    pub fn knn_search_into_buffers(
+        &self,
+        points: &[T],
+        k: usize,
+        buffers: &mut SearchBuffers<T::ResultType>,
+    ) -> Result<(), FlannError> {
+        if points.len() % self.point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: self.point_len,
+                got: points.len(),
+            });
+        }
+        let total_points = points.len() / self.point_len;
+        buffers.resize(total_points * k);
+        let found =
+            self.knn_search_into(points, k, &mut buffers.indices, &mut buffers.distances)?;
+        buffers.set_shape(total_points, found);
+        Ok(())
+    }
+
+    /// Performs k-NN search for `num` neighbors for several points on the rayon thread pool.
+    ///
+    /// The points are split into chunks that are searched in parallel with `cores` set to 1,
+    /// so FLANN doesn't start threads of its own. The neighbors are returned in the same
+    /// order as the points.
+    ///
+    /// This assumes points are already in a slice of memory
+    /// in component order where there are `point_len` components.
+    #[cfg(feature = "rayon")]
+    pub fn par_find_many_nearest_neighbors(
+        &self,
+        num: usize,
+        points: &[T],
+    ) -> Result<KnnResults<T::ResultType>, FlannError>
+    where
+        T: Sync,
+        T::ResultType: Send,
+    {
+        if points.len() % self.point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: self.point_len,
+                got: points.len(),
+            });
+        }
+        let mut flann_params = self.parameters;
+        flann_params.cores = 1;
+        let chunks = points
+            .par_chunks(PARALLEL_CHUNK_LEN * self.point_len)
+            .map(|chunk| self.find_many_nearest_neighbors_flat_raw(num, chunk, flann_params))
+            .collect::<Result<Vec<_>, FlannError>>()?;
+        Ok(KnnResults::concat(num.min(self.len()), chunks))
+    }
+
+    fn find_many_nearest_neighbors_flat_raw(
+        &self,
+        num: usize,
+        points: &[T],
+        flann_params: raw::FLANNParameters,
+    ) -> Result<KnnResults<T::ResultType>, FlannError> {
+        if points.len() % self.point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: self.point_len,
+                got: points.len(),
+            });
+        }
+        let num = num.min(self.len());
+        let total_points = points.len() / self.point_len;
+        let mut indices = vec![0; num * total_points];
+        let mut distances = vec![T::ResultType::default(); num * total_points];
+        self.knn_search_into_raw(points, num, &mut indices, &mut distances, flann_params)?;
+        Ok(KnnResults::from_flat(num, total_points, indices, distances))
     }
 }
@@ -1,4 +1,9 @@
+use bundle;
+use saved_index::TempFile;
 use slice_index::SliceIndex;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
 use FlannError;
 use Indexable;
 use Parameters;
@@ -41,16 +46,16 @@ impl<T: Indexable> VecIndex<T> {
         I: IntoIterator<Item = P>,
         P: IntoIterator<Item = T>,
     {
-        let mut points_vec = Vec::new();
-        for point in points {
-            let count = point.into_iter().map(|d| points_vec.push(d)).count();
-            if count != point_len {
-                return Err(FlannError::InvalidPointDimensionality {
-                    expected: point_len,
-                    got: count,
-                });
-            }
-        }
+        Self::from_flat(point_len, flatten_points(point_len, points)?, parameters)
+    }
+
+    /// Makes a new index that owns points that are in a `Vec` in component
+    /// order where there are `point_len` components.
+    pub(crate) fn from_flat(
+        point_len: usize,
+        points_vec: Vec<T>,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError> {
         if points_vec.is_empty() {
             return Err(FlannError::ZeroInputPoints);
         }
@@ -65,6 +70,82 @@ impl<T: Indexable> VecIndex<T> {
         })
     }
 
+    /// Loads an index previously written with `save` for the given points.
+    ///
+    /// The points must be the same ones the index was saved with, since FLANN
+    /// does not store the dataset in the index file.
+    pub fn load<Q, I, P>(
+        path: Q,
+        point_len: usize,
+        points: I,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError>
+    where
+        Q: AsRef<Path>,
+        I: IntoIterator<Item = P>,
+        P: IntoIterator<Item = T>,
+    {
+        let points_vec = flatten_points(point_len, points)?;
+        let index = SliceIndex::load(
+            path,
+            point_len,
+            unsafe { std::mem::transmute(&points_vec[..]) },
+            parameters,
+        )?;
+        Ok(Self {
+            storage: vec![points_vec],
+            slice_index: Some(index),
+        })
+    }
+
+    /// Loads an index from a bundle written with `save_bundle`.
+    pub fn load_bundle<P: AsRef<Path>>(path: P) -> Result<Self, FlannError> {
+        let file = File::open(path).map_err(|_| FlannError::FailedToReadBundle)?;
+        let file_len = file
+            .metadata()
+            .map_err(|_| FlannError::FailedToReadBundle)?
+            .len();
+        Self::read_bundle(&mut BufReader::new(file), file_len)
+    }
+
+    /// Saves the points, parameters and FLANN index together in one file
+    /// so that the index can be restored with `load_bundle`.
+    pub fn save_bundle<P: AsRef<Path>>(&self, path: P) -> Result<(), FlannError> {
+        let file = File::create(path).map_err(|_| FlannError::FailedToWriteBundle)?;
+        self.write_bundle(&mut BufWriter::new(file))
+    }
+
+    pub(crate) fn read_bundle<R: Read>(reader: &mut R, max_len: u64) -> Result<Self, FlannError> {
+        let bundle = bundle::read::<T, _>(reader, max_len)?;
+        let index = SliceIndex::load(
+            bundle.index.path(),
+            bundle.point_len,
+            unsafe { std::mem::transmute(&bundle.points[..]) },
+            bundle.parameters,
+        )?;
+        let mut index = Self {
+            storage: vec![bundle.points],
+            slice_index: Some(index),
+        };
+        for idx in bundle.removed {
+            index.remove(idx)?;
+        }
+        Ok(index)
+    }
+
+    pub(crate) fn write_bundle<W: Write>(&self, writer: &mut W) -> Result<(), FlannError> {
+        let (index, _) = TempFile::new().map_err(|_| FlannError::FailedToWriteBundle)?;
+        self.save(index.path())?;
+        bundle::write(
+            writer,
+            self.point_len,
+            &self.parameters(),
+            &self.storage,
+            &self.removed_ids(),
+            index.path(),
+        )
+    }
+
     /// Adds a point to the index.
     pub fn add(&mut self, point: Vec<T>) -> Result<(), FlannError> {
         self.slice_index
@@ -81,18 +162,39 @@ impl<T: Indexable> VecIndex<T> {
         I: IntoIterator<Item = P>,
         P: IntoIterator<Item = T>,
     {
-        let mut points_vec = Vec::new();
-        for point in points {
-            let count = point.into_iter().map(|d| points_vec.push(d)).count();
-            if count != self.point_len {
-                return Err(FlannError::InvalidPointDimensionality {
-                    expected: self.point_len,
-                    got: count,
-                });
-            }
-        }
+        let points_vec = flatten_points(self.point_len, points)?;
         self.add_many_slices(unsafe { std::mem::transmute(&points_vec[..]) })?;
         self.storage.push(points_vec);
         Ok(())
     }
+
+    /// Rebuilds the index from the points that weren't removed with the
+    /// parameters that the index uses, dropping the removed points.
+    ///
+    /// Returns the new id of every old id, or `None` for removed points.
+    pub fn compact(&mut self) -> Result<Vec<Option<usize>>, FlannError> {
+        let (index, remap) = SliceIndex::compact(self)?;
+        *self = index;
+        Ok(remap)
+    }
+}
+
+/// Collects points into a flat `Vec` in component order, checking that every
+/// point has `point_len` components.
+fn flatten_points<T, I, P>(point_len: usize, points: I) -> Result<Vec<T>, FlannError>
+where
+    I: IntoIterator<Item = P>,
+    P: IntoIterator<Item = T>,
+{
+    let mut points_vec = Vec::new();
+    for point in points {
+        let count = point.into_iter().map(|d| points_vec.push(d)).count();
+        if count != point_len {
+            return Err(FlannError::InvalidPointDimensionality {
+                expected: point_len,
+                got: count,
+            });
+        }
+    }
+    Ok(points_vec)
 }
@@ -0,0 +1,228 @@
+use bundle;
+use clustering::compute_cluster_centers;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use vec_index::VecIndex;
+use FlannError;
+use Indexable;
+use Parameters;
+
+const MAGIC: &[u8; 8] = b"FLANNVOC";
+const VERSION: u32 = 1;
+
+/// A bag-of-visual-words vocabulary.
+///
+/// The words are k-means cluster centers of a set of training descriptors.
+/// Descriptors of type `T` are quantized to the closest word, which is
+/// found with a FLANN index over the words.
+pub struct Vocabulary<T: Indexable>
+where
+    T::ResultType: Indexable + 'static,
+{
+    index: VecIndex<T::ResultType>,
+    idf: Option<Vec<f32>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Vocabulary<T>
+where
+    T: Indexable + Into<T::ResultType>,
+    T::ResultType: Indexable + 'static,
+{
+    /// Builds a vocabulary of up to `num_words` words by clustering descriptors
+    /// that are in a slice of memory in component order where there are
+    /// `point_len` components.
+    ///
+    /// `parameters` controls both the clustering and the index over the words.
+    /// FLANN may produce fewer words than requested, see `compute_cluster_centers`.
+    pub fn new(
+        point_len: usize,
+        descriptors: &[T],
+        num_words: usize,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError> {
+        let words = compute_cluster_centers(point_len, descriptors, num_words, &parameters)?;
+        Self::from_words(point_len, words, parameters)
+    }
+
+    /// Makes a vocabulary from words that are in a `Vec` in component order
+    /// where there are `point_len` components.
+    pub fn from_words(
+        point_len: usize,
+        words: Vec<T::ResultType>,
+        parameters: Parameters,
+    ) -> Result<Self, FlannError> {
+        if point_len == 0 || words.len() % point_len != 0 {
+            return Err(FlannError::InvalidFlatPointsLen {
+                expected: point_len,
+                got: words.len(),
+            });
+        }
+        let index = VecIndex::new(
+            point_len,
+            words.chunks(point_len).map(|word| word.to_vec()),
+            parameters,
+        )?;
+        Ok(Self {
+            index,
+            idf: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Loads a vocabulary written with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FlannError> {
+        let file = File::open(path).map_err(|_| FlannError::FailedToReadBundle)?;
+        let file_len = file
+            .metadata()
+            .map_err(|_| FlannError::FailedToReadBundle)?
+            .len();
+        let r = &mut BufReader::new(file);
+        let mut magic = [0u8; 8];
+        bundle::read_exact(r, &mut magic)?;
+        if &magic != MAGIC {
+            return Err(FlannError::InvalidBundle);
+        }
+        let version = u32::from_ne_bytes(bundle::read_array(r)?);
+        if version != VERSION {
+            return Err(FlannError::UnsupportedBundleVersion { version });
+        }
+        let idf_len = u64::from_ne_bytes(bundle::read_array(r)?);
+        let idf = if idf_len == 0 {
+            None
+        } else {
+            if idf_len > file_len / 4 {
+                return Err(FlannError::InvalidBundle);
+            }
+            let idf = (0..idf_len)
+                .map(|_| Ok(f32::from_ne_bytes(bundle::read_array(r)?)))
+                .collect::<Result<Vec<f32>, FlannError>>()?;
+            Some(idf)
+        };
+        let index = VecIndex::read_bundle(r, file_len)?;
+        if idf.as_ref().is_some_and(|idf| idf.len() != index.len()) {
+            return Err(FlannError::InvalidBundle);
+        }
+        Ok(Self {
+            index,
+            idf,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Saves the words, the index over them and the idf weights to a file
+    /// so that the vocabulary can be restored with `load`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FlannError> {
+        let file = File::create(path).map_err(|_| FlannError::FailedToWriteBundle)?;
+        let writer = &mut BufWriter::new(file);
+        let idf = self.idf.as_ref().map_or(&[][..], |idf| &idf[..]);
+        let mut header = Vec::with_capacity(20 + 4 * idf.len());
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_ne_bytes());
+        header.extend_from_slice(&(idf.len() as u64).to_ne_bytes());
+        for weight in idf {
+            header.extend_from_slice(&weight.to_ne_bytes());
+        }
+        writer
+            .write_all(&header)
+            .map_err(|_| FlannError::FailedToWriteBundle)?;
+        self.index.write_bundle(writer)
+    }
+
+    /// The number of words in the vocabulary.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the word with index `idx`.
+    pub fn word(&self, idx: usize) -> Option<&[T::ResultType]> {
+        self.index.get(idx)
+    }
+
+    /// The inverse document frequency weight of every word, if computed.
+    pub fn idf(&self) -> Option<&[f32]> {
+        self.idf.as_ref().map(|idf| &idf[..])
+    }
+
+    /// Finds the closest word for every descriptor.
+    ///
+    /// The descriptors must be in a slice of memory in component order
+    /// where there are `point_len` components.
+    pub fn quantize(&self, descriptors: &[T]) -> Result<Vec<usize>, FlannError> {
+        let descriptors: Vec<T::ResultType> = descriptors.iter().cloned().map(Into::into).collect();
+        let neighbors = self
+            .index
+            .find_many_nearest_neighbors_flat(1, &descriptors)?;
+        let words = neighbors
+            .iter()
+            .map(|neighbors| neighbors.first().map_or(0, |neighbor| neighbor.index))
+            .collect();
+        Ok(words)
+    }
+
+    /// Computes the normalized histogram of words for a set of descriptors.
+    ///
+    /// If idf weights were computed with `compute_idf`, every bin is
+    /// multiplied by the weight of its word, which gives tf-idf weighting.
+    pub fn histogram(&self, descriptors: &[T]) -> Result<Vec<f32>, FlannError> {
+        let words = self.quantize(descriptors)?;
+        let mut histogram = vec![0.0; self.len()];
+        for &word in &words {
+            histogram[word] += 1.0;
+        }
+        if !words.is_empty() {
+            let total = words.len() as f32;
+            for bin in &mut histogram {
+                *bin /= total;
+            }
+        }
+        if let Some(ref idf) = self.idf {
+            for (bin, weight) in histogram.iter_mut().zip(idf) {
+                *bin *= weight;
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Computes the inverse document frequency weight of every word from a
+    /// set of documents, each given as its descriptors.
+    ///
+    /// The weight of a word is `ln(documents / documents containing the word)`,
+    /// or 0 if no document contains the word.
+    pub fn compute_idf<'a, I>(&mut self, documents: I) -> Result<(), FlannError>
+    where
+        I: IntoIterator<Item = &'a [T]>,
+        T: 'a,
+    {
+        let mut document_frequency = vec![0usize; self.len()];
+        let mut num_documents = 0;
+        for descriptors in documents {
+            let mut words = self.quantize(descriptors)?;
+            words.sort_unstable();
+            words.dedup();
+            for word in words {
+                document_frequency[word] += 1;
+            }
+            num_documents += 1;
+        }
+        self.idf = Some(
+            document_frequency
+                .into_iter()
+                .map(|frequency| {
+                    if frequency == 0 {
+                        0.0
+                    } else {
+                        (num_documents as f32 / frequency as f32).ln()
+                    }
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+}
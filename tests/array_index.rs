@@ -0,0 +1,59 @@
+extern crate flann;
+
+use flann::*;
+
+fn points() -> Vec<[f32; 3]> {
+    (0..100).map(|i| [i as f32, (i % 10) as f32, 0.5]).collect()
+}
+
+#[test]
+fn builds_adds_and_removes() {
+    let mut index = ArrayIndex::<f32, 3>::new(points(), Parameters::default()).unwrap();
+    assert_eq!(index.len(), 100);
+    assert_eq!(index.get(42), Some(&[42.0, 2.0, 0.5]));
+    index.add([500.0, 0.0, 0.0]).unwrap();
+    index
+        .add_multiple(vec![[600.0, 0.0, 0.0], [700.0, 0.0, 0.0]])
+        .unwrap();
+    assert_eq!(index.len(), 103);
+    assert_eq!(index.get(102), Some(&[700.0, 0.0, 0.0]));
+    index.remove(102).unwrap();
+    let neighbor = index.find_nearest_neighbor(&[690.0, 0.0, 0.0]).unwrap();
+    assert_eq!(neighbor.index, 101);
+}
+
+#[test]
+fn searches_arrays() {
+    let index = ArrayIndex::<f32, 3>::new(points(), Parameters::default()).unwrap();
+    let neighbors = index
+        .find_nearest_neighbors(3, &[10.1, 0.0, 0.5])
+        .unwrap()
+        .map(|neighbor| neighbor.index)
+        .collect::<Vec<_>>();
+    assert_eq!(neighbors[0], 10);
+    assert_eq!(
+        index
+            .find_nearest_neighbors_radius(10, 1.0, &[10.0, 0.0, 0.5])
+            .unwrap()
+            .count(),
+        1
+    );
+    let queries = [[3.0, 3.0, 0.5], [97.2, 7.0, 0.5]];
+    let neighbors = index.find_many_nearest_neighbors(1, &queries).unwrap();
+    let found = neighbors
+        .iter()
+        .map(|neighbors| neighbors[0].index)
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![3, 97]);
+}
+
+#[test]
+fn searches_into_buffers() {
+    let index = ArrayIndex::<f32, 3>::new(points(), Parameters::default()).unwrap();
+    let mut buffers = SearchBuffers::new();
+    index
+        .knn_search_into_buffers(&[[10.1, 0.0, 0.5], [55.0, 5.0, 0.5]], 1, &mut buffers)
+        .unwrap();
+    assert_eq!(buffers.get(0).unwrap().0, &[10]);
+    assert_eq!(buffers.get(1).unwrap().0, &[55]);
+}
@@ -0,0 +1,133 @@
+extern crate flann;
+
+use flann::*;
+
+fn descriptors(count: usize, seed: u64) -> Vec<[u8; 32]> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            let mut descriptor = [0u8; 32];
+            for byte in descriptor.iter_mut() {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                *byte = (state >> 56) as u8;
+            }
+            descriptor
+        })
+        .collect()
+}
+
+fn popcount_distance(a: &[u8; 32], b: &[u8; 32]) -> u32 {
+    a.iter().zip(b).map(|(a, b)| (a ^ b).count_ones()).sum()
+}
+
+fn brute_force(num: usize, points: &[[u8; 32]], query: &[u8; 32]) -> Vec<(u32, usize)> {
+    let mut neighbors = points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| (popcount_distance(point, query), index))
+        .collect::<Vec<_>>();
+    neighbors.sort();
+    neighbors.truncate(num);
+    neighbors
+}
+
+fn lsh_parameters() -> Parameters {
+    Parameters {
+        algorithm: Algorithm::Lsh,
+        table_number: 12,
+        key_size: 20,
+        multi_probe_level: 2,
+        ..Parameters::default()
+    }
+}
+
+#[test]
+fn linear_matches_brute_force() {
+    let points = descriptors(500, 1);
+    let queries = descriptors(20, 2);
+    let index = BinaryIndex::new(
+        points.clone(),
+        Parameters {
+            algorithm: Algorithm::Linear,
+            ..Parameters::default()
+        },
+    )
+    .unwrap();
+    let many = index.find_many_nearest_neighbors(5, &queries).unwrap();
+    for (query, many) in queries.iter().zip(&many) {
+        let expected = brute_force(5, &points, query);
+        let neighbors = index
+            .find_nearest_neighbors(5, query)
+            .unwrap()
+            .map(|neighbor| (neighbor.distance, neighbor.index))
+            .collect::<Vec<_>>();
+        assert_eq!(neighbors, expected);
+        let many = many
+            .iter()
+            .map(|neighbor| (neighbor.distance, neighbor.index))
+            .collect::<Vec<_>>();
+        assert_eq!(many, expected);
+    }
+}
+
+#[test]
+fn lsh_reports_hamming_distances() {
+    let points = descriptors(500, 3);
+    let index = BinaryIndex::new(points.clone(), lsh_parameters()).unwrap();
+    for query in descriptors(20, 4).iter().chain(&points[..20]) {
+        let neighbors = index
+            .find_nearest_neighbors(5, query)
+            .unwrap()
+            .collect::<Vec<_>>();
+        for pair in neighbors.windows(2) {
+            assert!(pair[0].distance <= pair[1].distance);
+        }
+        for neighbor in neighbors {
+            assert_eq!(
+                neighbor.distance,
+                popcount_distance(&points[neighbor.index], query)
+            );
+        }
+    }
+}
+
+#[test]
+fn lsh_finds_exact_matches() {
+    let mut points = descriptors(500, 5);
+    let mut index = BinaryIndex::new(points.clone(), lsh_parameters()).unwrap();
+    let added = descriptors(10, 6);
+    index.add_many(&added).unwrap();
+    points.extend_from_slice(&added);
+    assert_eq!(index.len(), 510);
+    for (i, point) in points.iter().enumerate() {
+        let neighbor = index.find_nearest_neighbor(point).unwrap();
+        assert_eq!(neighbor.index, i);
+        assert_eq!(neighbor.distance, 0);
+    }
+}
+
+#[test]
+fn lsh_clamps_huge_neighbor_counts() {
+    let points = descriptors(50, 8);
+    let index = BinaryIndex::new(points.clone(), lsh_parameters()).unwrap();
+    let neighbors = index
+        .find_nearest_neighbors(usize::MAX, &points[0])
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert!(neighbors.len() <= 50);
+    assert_eq!(neighbors[0].index, 0);
+    let many = index
+        .find_many_nearest_neighbors(usize::MAX / 2, &points[..2])
+        .unwrap();
+    assert_eq!(many.len(), 2);
+}
+
+#[test]
+fn rejects_other_algorithms() {
+    match BinaryIndex::new(descriptors(10, 7), Parameters::default()) {
+        Err(FlannError::InvalidAlgorithm { .. }) => {}
+        _ => panic!("expected invalid algorithm error"),
+    }
+}
@@ -0,0 +1,49 @@
+#[macro_use]
+extern crate assert_approx_eq;
+extern crate flann;
+#[macro_use]
+extern crate generic_array;
+
+use flann::*;
+
+#[test]
+fn finds_centers_of_separated_clusters() {
+    let points: [f32; 16] = [
+        0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0, 10.0, 10.0, 10.0, 11.0, 11.0, 10.0, 11.0, 11.0,
+    ];
+    let parameters = Parameters {
+        branching: 2,
+        ..Parameters::default()
+    };
+    let centers = compute_cluster_centers(2, &points[..], 2, &parameters).unwrap();
+    assert_eq!(centers.len(), 4);
+
+    let mut centers = centers.chunks(2).collect::<Vec<_>>();
+    centers.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
+    assert_approx_eq!(centers[0][0], 0.5);
+    assert_approx_eq!(centers[0][1], 0.5);
+    assert_approx_eq!(centers[1][0], 10.5);
+    assert_approx_eq!(centers[1][1], 10.5);
+}
+
+#[test]
+fn rounds_cluster_count_down() {
+    let points = (0..100)
+        .map(|i| arr![f32; i as f32, 0.0])
+        .collect::<Vec<_>>();
+    let parameters = Parameters {
+        branching: 4,
+        ..Parameters::default()
+    };
+    // With a branching factor of 4 the possible cluster counts are 1, 4, 7, 10...
+    let centers = compute_cluster_centers_array(&points, 9, &parameters).unwrap();
+    assert_eq!(centers.len(), 7);
+}
+
+#[test]
+fn rejects_zero_clusters() {
+    match compute_cluster_centers(1, &[1.0f32, 2.0], 0, &Parameters::default()) {
+        Err(FlannError::ZeroClusters) => {}
+        _ => panic!("expected zero clusters error"),
+    }
+}
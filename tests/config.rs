@@ -0,0 +1,95 @@
+extern crate flann;
+
+use flann::*;
+
+fn points() -> Vec<Vec<f32>> {
+    (0..100).map(|i| vec![i as f32, (i % 10) as f32]).collect()
+}
+
+#[test]
+fn builds_index_from_config() {
+    let config = IndexConfig::KMeans(KMeansParams {
+        branching: 4,
+        ..KMeansParams::default()
+    });
+    let parameters = Parameters::from(config);
+    assert_eq!(parameters.algorithm.as_raw(), Algorithm::KMeans.as_raw());
+    assert_eq!(parameters.branching, 4);
+    let index = VecIndex::new(2, points(), parameters).unwrap();
+    assert_eq!(index.find_nearest_neighbor(&[42.0, 2.0]).unwrap().index, 42);
+}
+
+#[test]
+fn reads_config_from_parameters() {
+    let parameters = Parameters::from(IndexConfig::Lsh(LshParams {
+        table_number: 6,
+        key_size: 12,
+        multi_probe_level: 1,
+    }));
+    match IndexConfig::from_parameters(&parameters) {
+        Some(IndexConfig::Lsh(lsh)) => {
+            assert_eq!(lsh.table_number, 6);
+            assert_eq!(lsh.key_size, 12);
+            assert_eq!(lsh.multi_probe_level, 1);
+        }
+        config => panic!("expected LSH config, got {:?}", config),
+    }
+}
+
+#[test]
+fn rejects_out_of_range_parameters() {
+    let invalid = [
+        (IndexConfig::KDTree(KDTreeParams { trees: 0 }), "trees"),
+        (
+            IndexConfig::KMeans(KMeansParams {
+                branching: 1,
+                ..KMeansParams::default()
+            }),
+            "branching",
+        ),
+        (
+            IndexConfig::Autotuned(AutotunedParams {
+                target_precision: 1.5,
+                ..AutotunedParams::default()
+            }),
+            "target_precision",
+        ),
+    ];
+    for &(config, name) in &invalid {
+        match VecIndex::new(2, points(), config.into()) {
+            Err(FlannError::InvalidParameter { parameter, .. }) => assert_eq!(parameter, name),
+            _ => panic!("expected invalid parameter error for {}", name),
+        }
+    }
+}
+
+#[test]
+fn ignores_fields_of_other_algorithms() {
+    let parameters = Parameters {
+        algorithm: Algorithm::KDTree,
+        branching: 0,
+        ..Parameters::default()
+    };
+    assert!(parameters.validate().is_ok());
+}
+
+#[test]
+fn validates_distance() {
+    let parameters = Parameters {
+        distance_type: DistanceType::Minkowski,
+        distance_order: 0,
+        ..Parameters::default()
+    };
+    match parameters.validate() {
+        Err(FlannError::InvalidParameter { parameter, .. }) => {
+            assert_eq!(parameter, "distance_order")
+        }
+        _ => panic!("expected invalid distance order error"),
+    }
+    let parameters = Parameters {
+        distance_type: DistanceType::Minkowski,
+        distance_order: 3,
+        ..Parameters::default()
+    };
+    assert!(parameters.validate().is_ok());
+}
@@ -22,16 +22,18 @@ fn builds_and_adds() {
     )
     .unwrap();
     assert_eq!(index.len(), 5);
-    index.add(Default::default());
+    index.add(Default::default()).unwrap();
     assert_eq!(index.len(), 6);
-    index.add_multiple(vec![]);
+    index.add_multiple(vec![]).unwrap();
     assert_eq!(index.len(), 6);
-    index.add_multiple(vec![
-        Default::default(),
-        Default::default(),
-        Default::default(),
-        Default::default(),
-    ]);
+    index
+        .add_multiple(vec![
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        ])
+        .unwrap();
     assert_eq!(index.len(), 10);
 }
 
@@ -49,16 +51,18 @@ fn get_accesses_right_item() {
     )
     .unwrap();
 
-    index.add(arr![f32; 16, 17, 18]);
+    index.add(arr![f32; 16, 17, 18]).unwrap();
 
-    index.add_multiple(vec![]);
+    index.add_multiple(vec![]).unwrap();
 
-    index.add_multiple(vec![
-        arr![f32; 19, 20, 21],
-        arr![f32; 22, 23, 24],
-        arr![f32; 25, 26, 27],
-        arr![f32; 28, 29, 30],
-    ]);
+    index
+        .add_multiple(vec![
+            arr![f32; 19, 20, 21],
+            arr![f32; 22, 23, 24],
+            arr![f32; 25, 26, 27],
+            arr![f32; 28, 29, 30],
+        ])
+        .unwrap();
 
     assert_eq!(index.get(0), Some(&arr![f32; 1.0, 2.0, 3.0]));
     assert_eq!(index.get(1), Some(&arr![f32; 4.0, 5.0, 6.0]));
@@ -75,7 +79,7 @@ fn get_accesses_right_item() {
 
 #[test]
 fn nearest_neighbor_returns_correct_item() {
-    let mut index = Index::<f32, typenum::U3>::new(
+    let index = Index::<f32, typenum::U3>::new(
         vec![
             arr![f32; 0, 0, 0],
             arr![f32; 0, 0, 1],
@@ -90,14 +94,62 @@ fn nearest_neighbor_returns_correct_item() {
     )
     .unwrap();
 
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; -1, -1, -1]).index, 0);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; -1, -1, 2]).index, 1);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; -1, 2, -1]).index, 2);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; -1, 2, 2]).index, 3);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; 2, -1, -1]).index, 4);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; 2, -1, 2]).index, 5);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; 2, 2, -1]).index, 6);
-    assert_eq!(index.find_nearest_neighbor(&arr![f32; 2, 2, 2]).index, 7);
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; -1, -1, -1])
+            .unwrap()
+            .index,
+        0
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; -1, -1, 2])
+            .unwrap()
+            .index,
+        1
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; -1, 2, -1])
+            .unwrap()
+            .index,
+        2
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; -1, 2, 2])
+            .unwrap()
+            .index,
+        3
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; 2, -1, -1])
+            .unwrap()
+            .index,
+        4
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; 2, -1, 2])
+            .unwrap()
+            .index,
+        5
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; 2, 2, -1])
+            .unwrap()
+            .index,
+        6
+    );
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; 2, 2, 2])
+            .unwrap()
+            .index,
+        7
+    );
 }
 
 #[test]
@@ -117,9 +169,9 @@ fn nearest_neighbors_returns_correct_item() {
     ];
     let mut index = Point2::new(vec![Default::default()], Parameters::default()).unwrap();
     for v in data.clone() {
-        index.add(v);
+        index.add(v).unwrap();
     }
-    let nearest_neighbors = index.find_many_nearest_neighbors(3, &data);
+    let nearest_neighbors = index.find_many_nearest_neighbors(3, &data).unwrap();
 
     let indices = [
         [1, 2, 9],
@@ -133,7 +185,7 @@ fn nearest_neighbors_returns_correct_item() {
         [9, 2, 1],
         [10, 4, 3],
     ];
-    let distances_squared = [
+    let distances = [
         [0.0, 26674.0, 62010.0],
         [0.0, 12340.0, 26674.0],
         [0.0, 17828.0, 55370.0],
@@ -145,14 +197,14 @@ fn nearest_neighbors_returns_correct_item() {
         [0.0, 12340.0, 62010.0],
         [0.0, 13562.0, 17828.0],
     ];
-    for (neighbors, indices, distances_squared) in izip!(
+    for (neighbors, indices, distances) in izip!(
         (&nearest_neighbors).into_iter(),
         indices.iter(),
-        distances_squared.iter()
+        distances.iter()
     ) {
-        for (neighbor, &index, distance_squared) in izip!(neighbors, indices, distances_squared) {
+        for (neighbor, &index, distance) in izip!(neighbors, indices, distances) {
             assert_eq!(neighbor.index, index);
-            assert_approx_eq!(neighbor.distance_squared, distance_squared);
+            assert_approx_eq!(neighbor.distance, distance);
         }
     }
 }
@@ -161,22 +213,22 @@ fn nearest_neighbors_returns_correct_item() {
 fn nearest_neighbors_get_truncated() {
     type Point2 = Index<f32, typenum::U2>;
     let data = vec![arr![f32; 0, 0], arr![f32; 1, 1], arr![f32; 2, 2]];
-    let mut index = Point2::new(
+    let index = Point2::new(
         vec![Default::default(), Default::default()],
         Parameters::default(),
     )
     .unwrap();
 
-    for mut it in &index.find_many_nearest_neighbors(4, &data) {
-        assert!(it.next().is_some());
-        assert!(it.next().is_some());
-        assert!(it.next().is_none());
+    let neighbors = index.find_many_nearest_neighbors(4, &data).unwrap();
+    assert_eq!(neighbors.k(), 2);
+    for neighbors in &neighbors {
+        assert_eq!(neighbors.len(), 2);
     }
 }
 
 #[test]
 fn search_radius_returns_correct_item() {
-    let mut index = Index::<f32, typenum::U3>::new(
+    let index = Index::<f32, typenum::U3>::new(
         vec![
             arr![f32; 0, 0, 0],
             arr![f32; 0, 0, 1],
@@ -193,6 +245,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 1.1, &arr![f32; 0, 0, -1])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -200,6 +253,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 1.1, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -207,6 +261,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 10.0, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -214,6 +269,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(0, 10.0, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -221,6 +277,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 2.1, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -228,6 +285,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 3.1, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -235,6 +293,7 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 4.1, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
@@ -242,8 +301,101 @@ fn search_radius_returns_correct_item() {
 
     let mut indices = index
         .find_nearest_neighbors_radius(10, 6.1, &arr![f32; 2, 0, 0])
+        .unwrap()
         .map(|v| v.index)
         .collect::<Vec<usize>>();
     indices.sort();
     assert_eq!(indices, vec![0, 1, 2, 3, 4, 5, 6, 7]);
 }
+
+#[test]
+fn saves_and_loads() {
+    let points = vec![
+        arr![f32; 0, 0, 0],
+        arr![f32; 0, 0, 1],
+        arr![f32; 0, 1, 0],
+        arr![f32; 0, 1, 1],
+        arr![f32; 1, 0, 0],
+        arr![f32; 1, 0, 1],
+        arr![f32; 1, 1, 0],
+        arr![f32; 1, 1, 1],
+    ];
+    let path = std::env::temp_dir().join("flann_index_saves_and_loads.idx");
+    let index = Index::<f32, typenum::U3>::new(points.clone(), Parameters::default()).unwrap();
+    index.save(&path).unwrap();
+
+    let index =
+        Index::<f32, typenum::U3>::load(&path, points.clone(), Parameters::default()).unwrap();
+    assert_eq!(index.len(), 8);
+    assert_eq!(index.get(5), Some(&arr![f32; 1, 0, 1]));
+    assert_eq!(
+        index
+            .find_nearest_neighbor(&arr![f32; 2, 2, 2])
+            .unwrap()
+            .index,
+        7
+    );
+
+    assert!(
+        Index::<f32, typenum::U3>::load(&path, points[..4].to_vec(), Parameters::default())
+            .is_err()
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn search_with_params_overrides_checks() {
+    let index = Index::<f32, typenum::U3>::new(
+        vec![
+            arr![f32; 0, 0, 0],
+            arr![f32; 0, 0, 1],
+            arr![f32; 0, 1, 0],
+            arr![f32; 1, 0, 0],
+        ],
+        Parameters::default(),
+    )
+    .unwrap();
+    let params = SearchParams {
+        checks: Checks::Unlimited,
+        ..SearchParams::default()
+    };
+
+    let indices = index
+        .find_nearest_neighbors_with_params(2, &arr![f32; 0, 0, 2], &params)
+        .unwrap()
+        .map(|n| n.index)
+        .collect::<Vec<usize>>();
+    assert_eq!(indices, vec![1, 0]);
+    let mut indices = index
+        .find_nearest_neighbors_radius_with_params(10, 1.1, &arr![f32; 0, 0, 0], &params)
+        .unwrap()
+        .map(|n| n.index)
+        .collect::<Vec<usize>>();
+    indices.sort();
+    assert_eq!(indices, vec![0, 1, 2, 3]);
+    for neighbors in &index
+        .find_many_nearest_neighbors_with_params(
+            1,
+            &[arr![f32; 2, 0, 0], arr![f32; 0, 2, 0]],
+            &params,
+        )
+        .unwrap()
+    {
+        assert_eq!(neighbors.len(), 1);
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_search_returns_results_in_order() {
+    let points = (0..200)
+        .map(|i| arr![f32; i as f32, 0.0])
+        .collect::<Vec<_>>();
+    let index = Index::<f32, typenum::U2>::new(points.clone(), Parameters::default()).unwrap();
+
+    let neighbors = index.par_find_many_nearest_neighbors(1, &points).unwrap();
+    assert_eq!(neighbors.len(), 200);
+    for (i, neighbors) in neighbors.iter().enumerate() {
+        assert_eq!(neighbors[0].index, i);
+    }
+}
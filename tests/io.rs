@@ -0,0 +1,238 @@
+extern crate flann;
+
+use flann::io::*;
+use flann::*;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("flann-io-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn roundtrips_fvecs() {
+    let path = temp_path("roundtrip.fvecs");
+    let points = (0..30).map(|i| i as f32 * 0.5).collect::<Vec<_>>();
+    save_fvecs(&path, 3, &points).unwrap();
+    let dataset = load_fvecs(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(dataset.point_len, 3);
+    assert_eq!(dataset.len(), 10);
+    assert_eq!(dataset.points, points);
+    assert_eq!(dataset.get(2).unwrap(), &[3.0, 3.5, 4.0]);
+}
+
+#[test]
+fn roundtrips_ivecs_and_bvecs() {
+    let path = temp_path("roundtrip.ivecs");
+    let points = vec![-1, 0, 1, i32::MAX, i32::MIN, 7];
+    save_ivecs(&path, 2, &points).unwrap();
+    assert_eq!(load_ivecs(&path).unwrap().points, points);
+    std::fs::remove_file(&path).unwrap();
+
+    let path = temp_path("roundtrip.bvecs");
+    let points = (0..=255).collect::<Vec<u8>>();
+    save_bvecs(&path, 128, &points).unwrap();
+    let dataset = load_bvecs(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(dataset.point_len, 128);
+    assert_eq!(dataset.points, points);
+}
+
+#[test]
+fn writes_little_endian_records() {
+    let mut bytes = Vec::new();
+    write_vecs(&mut bytes, 2, &[1.0f32, -2.0]).unwrap();
+    let mut expected = 2i32.to_le_bytes().to_vec();
+    expected.extend_from_slice(&1.0f32.to_le_bytes());
+    expected.extend_from_slice(&(-2.0f32).to_le_bytes());
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn streams_chunks_into_index() {
+    let mut bytes = Vec::new();
+    let points = (0..100)
+        .flat_map(|i| vec![i as f32, 0.0])
+        .collect::<Vec<_>>();
+    write_vecs(&mut bytes, 2, &points).unwrap();
+
+    let mut reader = VecsReader::<f32, _>::new(&bytes[..]);
+    let first = reader.read_chunk(60).unwrap();
+    assert_eq!(reader.point_len(), Some(2));
+    let mut index = VecIndex::new(
+        2,
+        first.chunks(2).map(|p| p.to_vec()),
+        Parameters::default(),
+    )
+    .unwrap();
+    loop {
+        let chunk = reader.read_chunk(30).unwrap();
+        if chunk.is_empty() {
+            break;
+        }
+        index.add_many(chunk.chunks(2).map(|p| p.to_vec())).unwrap();
+    }
+    assert_eq!(index.len(), 100);
+    assert_eq!(index.find_nearest_neighbor(&[81.2, 0.0]).unwrap().index, 81);
+}
+
+#[test]
+fn rejects_inconsistent_dimensionality() {
+    let mut bytes = Vec::new();
+    write_vecs(&mut bytes, 2, &[1u8, 2]).unwrap();
+    write_vecs(&mut bytes, 3, &[1u8, 2, 3]).unwrap();
+    match VecsReader::<u8, _>::new(&bytes[..]).read_all() {
+        Err(FlannError::InvalidPointDimensionality {
+            expected: 2,
+            got: 3,
+        }) => {}
+        _ => panic!("expected invalid dimensionality error"),
+    }
+}
+
+#[test]
+fn rejects_truncated_records() {
+    let mut bytes = Vec::new();
+    write_vecs(&mut bytes, 4, &[1i32, 2, 3, 4]).unwrap();
+    bytes.pop();
+    match VecsReader::<i32, _>::new(&bytes[..]).read_all() {
+        Err(FlannError::InvalidDataset) => {}
+        _ => panic!("expected invalid dataset error"),
+    }
+}
+
+#[test]
+fn rejects_huge_record_headers() {
+    let mut bytes = i32::MAX.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&[0; 16]);
+    match VecsReader::<f32, _>::new(&bytes[..]).read_all() {
+        Err(FlannError::InvalidDataset) => {}
+        _ => panic!("expected invalid dataset error"),
+    }
+    let len = bytes.len() as u64;
+    match VecsReader::<f32, _>::with_max_len(&bytes[..], len).read_all() {
+        Err(FlannError::InvalidDataset) => {}
+        _ => panic!("expected invalid dataset error"),
+    }
+
+    let path = temp_path("huge_header.fvecs");
+    std::fs::write(&path, &bytes).unwrap();
+    let loaded = load_fvecs(&path);
+    std::fs::remove_file(&path).unwrap();
+    match loaded {
+        Err(FlannError::InvalidDataset) => {}
+        _ => panic!("expected invalid dataset error"),
+    }
+}
+
+#[test]
+fn roundtrips_npy() {
+    let path = temp_path("roundtrip.npy");
+    let points = (0..12).map(|i| i as f64 / 3.0).collect::<Vec<_>>();
+    save_npy(&path, 4, &points).unwrap();
+    let dataset = load_npy::<f64, _>(&path).unwrap();
+    assert_eq!(dataset.point_len, 4);
+    assert_eq!(dataset.len(), 3);
+    assert_eq!(dataset.points, points);
+    match load_npy::<f32, _>(&path) {
+        Err(FlannError::DatasetTypeMismatch) => {}
+        _ => panic!("expected type mismatch error"),
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reads_npy_headers() {
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, 3, &[1u8, 2, 3, 4, 5, 6]).unwrap();
+    assert_eq!(bytes.len() % 64, 6);
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert!(header.starts_with("{'descr': '|u1', 'fortran_order': False, 'shape': (2, 3), }"));
+
+    let npy = |header: &str, data: &[u8]| {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    };
+    let big_endian = npy(
+        "{'descr': '>i4', 'fortran_order': False, 'shape': (1, 2), }\n",
+        &[0, 0, 0, 1, 255, 255, 255, 254],
+    );
+    assert_eq!(
+        read_npy::<i32, _>(&mut &big_endian[..]).unwrap().points,
+        vec![1, -2]
+    );
+    let fortran = npy(
+        "{'descr': '<i4', 'fortran_order': True, 'shape': (1, 1), }\n",
+        &[1, 0, 0, 0],
+    );
+    let one_dimensional = npy(
+        "{'descr': '<i4', 'fortran_order': False, 'shape': (1,), }\n",
+        &[1, 0, 0, 0],
+    );
+    let truncated = npy(
+        "{'descr': '<i4', 'fortran_order': False, 'shape': (1, 1), }\n",
+        &[1, 0],
+    );
+    for bytes in &[fortran, one_dimensional, truncated] {
+        match read_npy::<i32, _>(&mut &bytes[..]) {
+            Err(FlannError::InvalidDataset) => {}
+            _ => panic!("expected invalid dataset error"),
+        }
+    }
+}
+
+#[test]
+fn roundtrips_npz() {
+    let mut writer = NpzWriter::new(std::io::Cursor::new(Vec::new()));
+    writer.add("base", 2, &[1.5f32, 2.5, 3.5, 4.5]).unwrap();
+    writer.add("labels", 1, &[7i32, 8]).unwrap();
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let mut reader = NpzReader::new(std::io::Cursor::new(bytes)).unwrap();
+    assert_eq!(reader.names().collect::<Vec<_>>(), vec!["base", "labels"]);
+    let base = reader.read::<f32>("base").unwrap();
+    assert_eq!(base.point_len, 2);
+    assert_eq!(base.points, vec![1.5, 2.5, 3.5, 4.5]);
+    assert_eq!(reader.read::<i32>("labels").unwrap().points, vec![7, 8]);
+}
+
+#[test]
+fn saves_search_results() {
+    let points = (0..50).map(|i| i as f32).collect::<Vec<_>>();
+    let index = SliceIndex::new(1, &points[..], Parameters::default()).unwrap();
+    let queries = [10.2f32, 30.7, 48.9];
+    let neighbors = index
+        .find_many_nearest_neighbors_flat(3, &queries[..])
+        .unwrap();
+    let path = temp_path("neighbors.npz");
+    save_neighbors_npz(&path, &neighbors).unwrap();
+    let indices = load_npz::<i64, _>(&path, "indices").unwrap();
+    let distances = load_npz::<f32, _>(&path, "distances").unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(indices.point_len, 3);
+    assert_eq!(indices.get(1).unwrap(), &[31, 30, 32]);
+    assert_eq!(distances.len(), 3);
+    assert!(distances.points[0] < distances.points[1]);
+}
+
+#[cfg(feature = "memmap2")]
+#[test]
+fn maps_files() {
+    let path = temp_path("mapped.fvecs");
+    let points = (0..40).map(|i| i as f32).collect::<Vec<_>>();
+    save_fvecs(&path, 4, &points).unwrap();
+    {
+        let mapped = MappedVecs::<f32>::open(&path).unwrap();
+        assert_eq!(mapped.point_len(), 4);
+        assert_eq!(mapped.len(), 10);
+        assert_eq!(mapped.point(3).unwrap(), vec![12.0, 13.0, 14.0, 15.0]);
+        assert_eq!(mapped.points(8..10).unwrap(), points[32..].to_vec());
+        assert!(mapped.points(9..11).is_none());
+        assert_eq!(mapped.to_dataset().points, points);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,88 @@
+extern crate flann;
+
+use flann::*;
+
+fn index() -> KeyedIndex<String, f32> {
+    let mut index = KeyedIndex::new(2, Parameters::default()).unwrap();
+    for i in 0..20 {
+        index
+            .insert(format!("row-{}", i), vec![i as f32, 0.0])
+            .unwrap();
+    }
+    index
+}
+
+#[test]
+fn finds_keys_of_neighbors() {
+    let index = index();
+    assert_eq!(index.len(), 20);
+    assert!(index.contains_key(&"row-3".to_owned()));
+    assert_eq!(index.get(&"row-3".to_owned()), Some(&[3.0, 0.0][..]));
+    let neighbor = index.find_nearest_neighbor(&[7.2, 0.0]).unwrap().unwrap();
+    assert_eq!(neighbor.key, "row-7");
+    let keys = index
+        .find_nearest_neighbors(3, &[7.2, 0.0])
+        .unwrap()
+        .map(|neighbor| neighbor.key.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["row-7", "row-8", "row-6"]);
+}
+
+#[test]
+fn removed_points_are_not_found() {
+    let mut index = index();
+    assert!(index.remove(&"row-7".to_owned()).unwrap());
+    assert!(!index.remove(&"row-7".to_owned()).unwrap());
+    assert!(!index.contains_key(&"row-7".to_owned()));
+    assert_eq!(index.get(&"row-7".to_owned()), None);
+    assert_eq!(index.len(), 19);
+    let keys = index
+        .find_nearest_neighbors(2, &[7.0, 0.0])
+        .unwrap()
+        .map(|neighbor| neighbor.key.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(keys.len(), 2);
+    assert!(!keys.contains(&"row-7".to_owned()));
+}
+
+#[test]
+fn insert_replaces_points() {
+    let mut index = index();
+    index.insert("row-3".to_owned(), vec![100.0, 0.0]).unwrap();
+    assert_eq!(index.len(), 20);
+    assert_eq!(index.get(&"row-3".to_owned()), Some(&[100.0, 0.0][..]));
+    let neighbor = index.find_nearest_neighbor(&[99.0, 0.0]).unwrap().unwrap();
+    assert_eq!(neighbor.key, "row-3");
+    let neighbor = index.find_nearest_neighbor(&[3.0, 0.0]).unwrap().unwrap();
+    assert_ne!(neighbor.key, "row-3");
+}
+
+#[test]
+fn empty_index_finds_nothing() {
+    let mut index = KeyedIndex::<u64, f32>::new(2, Parameters::default()).unwrap();
+    assert!(index.find_nearest_neighbor(&[0.0, 0.0]).unwrap().is_none());
+    index.insert(1, vec![0.0, 0.0]).unwrap();
+    index.remove(&1).unwrap();
+    assert!(index.is_empty());
+    assert_eq!(
+        index
+            .find_nearest_neighbors(5, &[0.0, 0.0])
+            .unwrap()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn finds_num_neighbors_after_many_removals() {
+    let mut index = index();
+    for i in 0..15 {
+        index.remove(&format!("row-{}", i)).unwrap();
+    }
+    let keys = index
+        .find_nearest_neighbors(3, &[0.0, 0.0])
+        .unwrap()
+        .map(|neighbor| neighbor.key.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["row-15", "row-16", "row-17"]);
+}
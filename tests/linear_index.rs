@@ -0,0 +1,217 @@
+#[macro_use]
+extern crate assert_approx_eq;
+extern crate flann;
+
+use flann::*;
+
+fn points() -> Vec<f32> {
+    (0..1000)
+        .flat_map(|i| vec![(i % 37) as f32, (i % 101) as f32 * 0.5, (i % 7) as f32])
+        .collect()
+}
+
+fn queries() -> Vec<f32> {
+    (0..50)
+        .flat_map(|i| vec![(i % 41) as f32 + 0.3, (i % 97) as f32 * 0.5 - 0.2, 1.5])
+        .collect()
+}
+
+#[test]
+fn matches_flann_linear_search() {
+    let points = points();
+    for &distance_type in &[DistanceType::Euclidean, DistanceType::Manhattan] {
+        let parameters = Parameters {
+            algorithm: Algorithm::Linear,
+            distance_type,
+            ..Parameters::default()
+        };
+        let flann = SliceIndex::new(3, &points[..], parameters.clone()).unwrap();
+        let linear = LinearIndex::new(3, points.clone(), parameters).unwrap();
+        assert_eq!(linear.len(), 1000);
+        let queries = queries();
+        for query in queries.chunks(3) {
+            let expected = flann.find_nearest_neighbors(5, query).unwrap();
+            let found = linear.find_nearest_neighbors(5, query).unwrap();
+            for (expected, found) in expected.zip(found) {
+                assert_approx_eq!(expected.distance, found.distance, 1e-3);
+            }
+        }
+    }
+}
+
+#[test]
+fn computes_hamming_distance_on_bytes() {
+    let parameters = Parameters {
+        distance_type: DistanceType::Hamming,
+        ..Parameters::default()
+    };
+    let index = LinearIndex::new(2, vec![0b0000_0000u8, 0, 0b1111_0000, 0b1], parameters).unwrap();
+    let neighbors = index
+        .find_nearest_neighbors(2, &[0b1111_0000, 0b11])
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(neighbors[0].index, 1);
+    assert_approx_eq!(neighbors[0].distance, 1.0);
+    assert_eq!(neighbors[1].index, 0);
+    assert_approx_eq!(neighbors[1].distance, 6.0);
+}
+
+#[test]
+fn radius_search_excludes_far_points() {
+    let index = LinearIndex::new(1, vec![0.0f32, 1.0, 2.0, 3.0], Parameters::default()).unwrap();
+    let neighbors = index
+        .find_nearest_neighbors_radius(10, 1.5, &[0.0])
+        .unwrap()
+        .map(|neighbor| neighbor.index)
+        .collect::<Vec<_>>();
+    assert_eq!(neighbors, vec![0, 1]);
+}
+
+#[test]
+fn radius_search_excludes_points_on_radius_like_flann() {
+    let points = vec![0.0f32, 1.0, 2.0, 3.0];
+    let parameters = Parameters {
+        algorithm: Algorithm::Linear,
+        ..Parameters::default()
+    };
+    let flann = SliceIndex::new(1, &points[..], parameters.clone()).unwrap();
+    let linear = LinearIndex::new(1, points.clone(), parameters).unwrap();
+    // The squared distance to point 1 is exactly the radius.
+    let expected = flann
+        .find_all_within_radius(1.0, &[0.0])
+        .unwrap()
+        .iter()
+        .map(|neighbor| neighbor.index)
+        .collect::<Vec<_>>();
+    let found = linear
+        .find_all_within_radius(1.0, &[0.0])
+        .unwrap()
+        .iter()
+        .map(|neighbor| neighbor.index)
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![0]);
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn orders_nan_distances_last() {
+    let index = LinearIndex::new(1, vec![f32::NAN, 2.0], Parameters::default()).unwrap();
+    let neighbors = index
+        .find_nearest_neighbors(2, &[0.0])
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(neighbors[0].index, 1);
+    assert!(neighbors[1].distance.is_nan());
+    assert_eq!(index.find_nearest_neighbor(&[0.0]).unwrap().index, 1);
+
+    let index = LinearIndex::new(1, vec![f32::NAN], Parameters::default()).unwrap();
+    let neighbor = index.find_nearest_neighbor(&[0.0]).unwrap();
+    assert_eq!(neighbor.index, 0);
+    assert!(neighbor.distance.is_nan());
+    assert!(index
+        .find_all_within_radius(f32::INFINITY, &[0.0])
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn reports_truncated_radius_search() {
+    let index = LinearIndex::new(1, vec![0.0f32, 1.0, 2.0, 3.0], Parameters::default()).unwrap();
+    let result = index.find_within_radius(1, 1.5, &[0.0]).unwrap();
+    assert!(result.truncated);
+    assert_eq!(result.neighbors.len(), 1);
+    assert_eq!(index.find_all_within_radius(4.5, &[0.0]).unwrap().len(), 3);
+}
+
+#[test]
+fn searches_many_points() {
+    let index = LinearIndex::new(1, vec![0.0f32, 1.0, 2.0, 3.0], Parameters::default()).unwrap();
+    let neighbors = index
+        .find_many_nearest_neighbors_flat(2, &[0.2, 2.9, 1.6])
+        .unwrap();
+    assert_eq!(neighbors.len(), 3);
+    assert_eq!(neighbors.row(1)[0].index, 3);
+    assert_eq!(neighbors.row(2)[0].index, 2);
+    let none = index
+        .find_many_nearest_neighbors_flat(0, &[0.2, 2.9])
+        .unwrap();
+    assert_eq!(none.len(), 2);
+    assert!(none.iter().all(|neighbors| neighbors.is_empty()));
+}
+
+#[test]
+fn removed_points_are_not_found() {
+    let mut index =
+        LinearIndex::new(1, vec![0.0f32, 1.0, 2.0, 3.0], Parameters::default()).unwrap();
+    index.remove(1).unwrap();
+    index.remove(1).unwrap();
+    assert!(index.remove(4).is_err());
+    assert_eq!(index.get(1), None);
+    assert_eq!(index.live_len(), 3);
+    assert_eq!(index.len(), 3);
+    assert_eq!(index.find_nearest_neighbor(&[1.1]).unwrap().index, 2);
+    let neighbors = index
+        .find_many_nearest_neighbors_flat(4, &[0.9, 2.1])
+        .unwrap();
+    assert_eq!(neighbors.row(0).len(), 3);
+    assert!(neighbors
+        .iter()
+        .flatten()
+        .all(|neighbor| neighbor.index != 1));
+}
+
+#[test]
+fn exact_index_has_full_recall() {
+    let points = points();
+    let parameters = Parameters {
+        algorithm: Algorithm::Linear,
+        ..Parameters::default()
+    };
+    let index = SliceIndex::new(3, &points[..], parameters.clone()).unwrap();
+    let ground_truth = LinearIndex::new(3, points.clone(), parameters).unwrap();
+    let report = evaluate_recall(&index, &ground_truth, &queries(), 5).unwrap();
+    assert_approx_eq!(report.recall, 1.0);
+    assert_approx_eq!(report.mean_distance_ratio, 1.0, 1e-3);
+    assert!(report.latency.min <= report.latency.median);
+    assert!(report.latency.median <= report.latency.max);
+}
+
+#[test]
+fn recall_needs_ground_truth_points() {
+    let points = vec![0.0f32, 1.0];
+    let index = SliceIndex::new(1, &points[..], Parameters::default()).unwrap();
+    let mut ground_truth = LinearIndex::new(1, points.clone(), Parameters::default()).unwrap();
+    ground_truth.remove(0).unwrap();
+    ground_truth.remove(1).unwrap();
+    match evaluate_recall(&index, &ground_truth, &[0.5], 1) {
+        Err(FlannError::InvalidParameter {
+            parameter: "ground_truth",
+            ..
+        }) => {}
+        _ => panic!("expected invalid ground truth error"),
+    }
+    match evaluate_recall(&index, &ground_truth, &[0.5], 0) {
+        Err(FlannError::InvalidParameter { parameter: "k", .. }) => {}
+        _ => panic!("expected invalid k error"),
+    }
+}
+
+#[test]
+fn approximate_index_has_partial_recall() {
+    let points = points();
+    let index = SliceIndex::new(
+        3,
+        &points[..],
+        Parameters {
+            algorithm: Algorithm::KDTree,
+            trees: 1,
+            checks: Checks::Exact(1),
+            ..Parameters::default()
+        },
+    )
+    .unwrap();
+    let ground_truth = LinearIndex::new(3, points.clone(), Parameters::default()).unwrap();
+    let report = evaluate_recall(&index, &ground_truth, &queries(), 10).unwrap();
+    assert!(report.recall > 0.0 && report.recall <= 1.0);
+    assert!(report.mean_distance_ratio >= 1.0 - 1e-3);
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "nalgebra")]
+
+extern crate flann;
+extern crate nalgebra;
+
+use flann::*;
+use nalgebra::{Point3, Vector2};
+
+fn points() -> Vec<Point3<f32>> {
+    (0..50)
+        .map(|i| Point3::new(i as f32, (i % 7) as f32, -(i as f32)))
+        .collect()
+}
+
+#[test]
+fn builds_from_points_and_looks_them_up() {
+    let points = points();
+    let mut index =
+        Index::<f32, typenum::U3>::from_points(points.clone(), Parameters::default()).unwrap();
+    assert_eq!(index.len(), 50);
+    let neighbor = index
+        .find_nearest_neighbor_point(&Point3::new(20.1, 6.0, -20.0))
+        .unwrap();
+    assert_eq!(neighbor.index, 20);
+    assert_eq!(
+        index.get_point::<Point3<f32>>(neighbor.index),
+        Some(&points[20])
+    );
+
+    index.add_point(&Point3::new(100.0, 0.0, 0.0)).unwrap();
+    index
+        .add_points(vec![
+            Point3::new(200.0, 0.0, 0.0),
+            Point3::new(300.0, 0.0, 0.0),
+        ])
+        .unwrap();
+    assert_eq!(index.len(), 53);
+    let nearest = index
+        .find_nearest_neighbors_point(2, &Point3::new(290.0, 0.0, 0.0))
+        .unwrap()
+        .map(|neighbor| *index.get_point::<Point3<f32>>(neighbor.index).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        nearest,
+        vec![Point3::new(300.0, 0.0, 0.0), Point3::new(200.0, 0.0, 0.0)]
+    );
+}
+
+#[test]
+fn searches_many_vectors() {
+    let vectors = (0..20)
+        .map(|i| Vector2::new(i as f64, 0.0))
+        .collect::<Vec<_>>();
+    let index = Index::<f64, typenum::U2>::from_points(vectors, Parameters::default()).unwrap();
+    let queries = [Vector2::new(3.2, 0.0), Vector2::new(17.9, 1.0)];
+    let neighbors = index
+        .find_many_nearest_neighbors_points(1, &queries)
+        .unwrap();
+    let found = neighbors
+        .iter()
+        .map(|neighbors| neighbors[0].index)
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![3, 18]);
+    assert_eq!(
+        index.get_point::<Vector2<f64>>(18),
+        Some(&Vector2::new(18.0, 0.0))
+    );
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "ndarray")]
+
+extern crate flann;
+extern crate ndarray;
+
+use flann::*;
+use ndarray::{Array2, ShapeBuilder};
+
+fn points() -> Array2<f32> {
+    Array2::from_shape_fn((100, 2), |(i, j)| (i * (j + 1)) as f32)
+}
+
+#[test]
+fn builds_from_standard_layout() {
+    let points = points();
+    let index = SliceIndex::from_array_view(points.view(), Parameters::default()).unwrap();
+    assert_eq!(index.len(), 100);
+    assert_eq!(index.get(7).unwrap(), &[7.0, 14.0]);
+}
+
+#[test]
+fn copies_non_contiguous_arrays() {
+    let points = points();
+    let transposed = points.t().to_owned();
+    let index = SliceIndex::from_array_view(transposed.t(), Parameters::default()).unwrap();
+    assert_eq!(index.get(7).unwrap(), &[7.0, 14.0]);
+
+    let mut fortran = Array2::zeros((100, 2).f());
+    fortran.assign(&points);
+    let index = SliceIndex::from_array_view(fortran.view(), Parameters::default()).unwrap();
+    assert_eq!(index.get(99).unwrap(), &[99.0, 198.0]);
+}
+
+#[test]
+fn searches_rows() {
+    let points = points();
+    let index = SliceIndex::from_array_view(points.view(), Parameters::default()).unwrap();
+    let queries = ndarray::arr2(&[[3.1, 6.0], [50.0, 100.4], [0.0, 0.0]]);
+    let (indices, distances) = index.search_knn_array(&queries.view(), 2).unwrap();
+    assert_eq!(indices.dim(), (3, 2));
+    assert_eq!(distances.dim(), (3, 2));
+    assert_eq!(indices.column(0).to_vec(), vec![3, 50, 0]);
+    assert_eq!(distances[[2, 0]], 0.0);
+    assert!(distances[[0, 0]] <= distances[[0, 1]]);
+
+    let (indices, _) = index.search_knn_array(&queries.view(), 0).unwrap();
+    assert_eq!(indices.dim(), (3, 0));
+}
+
+#[test]
+fn rejects_wrong_dimensionality() {
+    let points = points();
+    let index = SliceIndex::from_array_view(points.view(), Parameters::default()).unwrap();
+    let queries = Array2::<f32>::zeros((1, 3));
+    match index.search_knn_array(&queries.view(), 1) {
+        Err(FlannError::InvalidPointDimensionality {
+            expected: 2,
+            got: 3,
+        }) => {}
+        _ => panic!("expected invalid dimensionality error"),
+    }
+}
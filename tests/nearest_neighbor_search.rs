@@ -0,0 +1,113 @@
+extern crate flann;
+extern crate generic_array;
+
+use flann::*;
+use generic_array::GenericArray;
+
+fn points() -> Vec<f32> {
+    (0..100)
+        .flat_map(|i| vec![i as f32, (i % 10) as f32])
+        .collect()
+}
+
+fn backends(points: &[f32]) -> Vec<Box<dyn NearestNeighborSearch<f32> + '_>> {
+    let linear = Parameters {
+        algorithm: Algorithm::Linear,
+        ..Parameters::default()
+    };
+    vec![
+        Box::new(SliceIndex::new(2, points, linear.clone()).unwrap()),
+        Box::new(VecIndex::new(2, points.chunks(2).map(|p| p.to_vec()), linear.clone()).unwrap()),
+        Box::new(
+            Index::<f32, typenum::U2>::new(
+                points.chunks(2).map(GenericArray::clone_from_slice),
+                linear.clone(),
+            )
+            .unwrap(),
+        ),
+        Box::new(LinearIndex::new(2, points.to_vec(), linear).unwrap()),
+    ]
+}
+
+#[test]
+fn backends_agree() {
+    let points = points();
+    for index in backends(&points) {
+        assert_eq!(index.point_len(), 2);
+        assert_eq!(index.len(), 100);
+        assert_eq!(index.point(42), Some(&[42.0, 2.0][..]));
+        assert_eq!(
+            index.search_nearest_neighbor(&[10.2, 0.1]).unwrap().index,
+            10
+        );
+        let neighbors = index.search_nearest_neighbors(3, &[10.2, 0.1]).unwrap();
+        assert_eq!(neighbors.len(), 3);
+        assert_eq!(neighbors[0].index, 10);
+        let within = index.search_all_within_radius(1.5, &[50.0, 0.0]).unwrap();
+        assert_eq!(within.len(), 1);
+        let many = index
+            .search_many_nearest_neighbors(1, &[3.1, 3.0, 97.0, 7.2])
+            .unwrap();
+        assert_eq!(many.row(0)[0].index, 3);
+        assert_eq!(many.row(1)[0].index, 97);
+        assert!(index.search_nearest_neighbor(&[1.0]).is_err());
+    }
+}
+
+#[test]
+fn evaluates_recall_of_any_backend() {
+    let points = points();
+    let ground_truth = LinearIndex::new(2, points.clone(), Parameters::default()).unwrap();
+    let queries = [5.5, 4.0, 60.2, 1.0];
+    for index in backends(&points) {
+        let report = evaluate_recall(&*index, &ground_truth, &queries, 2).unwrap();
+        assert!(report.recall > 0.99);
+    }
+}
+
+#[test]
+fn backends_add_and_remove_points() {
+    let points = points();
+    for (i, mut index) in backends(&points).into_iter().enumerate() {
+        index.delete_point(10).unwrap();
+        assert_eq!(index.len(), 99);
+        assert_ne!(
+            index.search_nearest_neighbor(&[10.0, 0.0]).unwrap().index,
+            10
+        );
+        let added = index.insert_point(&[200.0, 0.0]);
+        if i == 0 {
+            // `SliceIndex` only borrows its points.
+            match added {
+                Err(FlannError::UnsupportedOperation { .. }) => {}
+                _ => panic!("SliceIndex added a point it doesn't borrow"),
+            }
+        } else {
+            added.unwrap();
+            assert_eq!(index.len(), 100);
+            assert_eq!(
+                index.search_nearest_neighbor(&[199.0, 0.0]).unwrap().index,
+                100
+            );
+        }
+        assert!(index.insert_point(&[1.0]).is_err());
+    }
+}
+
+#[test]
+fn mutates_concrete_index_through_trait() {
+    let points = points();
+    let mut index = Index::<f32, typenum::U2>::new(
+        points.chunks(2).map(GenericArray::clone_from_slice),
+        Parameters::default(),
+    )
+    .unwrap();
+    index.insert_point(&[200.0, 0.0][..]).unwrap();
+    index.delete_point(0).unwrap();
+    assert_eq!(
+        NearestNeighborSearch::search_nearest_neighbor(&index, &[199.0, 0.0])
+            .unwrap()
+            .index,
+        100
+    );
+}
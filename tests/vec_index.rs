@@ -134,7 +134,7 @@ fn get_accesses_right_item() {
 
 #[test]
 fn nearest_neighbor_returns_correct_item() {
-    let mut index: VecIndex<f32> = VecIndex::new(
+    let index: VecIndex<f32> = VecIndex::new(
         3,
         vec![
             vec![0.0, 0.0, 0.0],
@@ -238,7 +238,7 @@ fn nearest_neighbors_returns_correct_item() {
         [9, 2, 1],
         [10, 4, 3],
     ];
-    let distances_squared = [
+    let distances = [
         [0.0, 26674.0, 62010.0],
         [0.0, 12340.0, 26674.0],
         [0.0, 17828.0, 55370.0],
@@ -250,14 +250,14 @@ fn nearest_neighbors_returns_correct_item() {
         [0.0, 12340.0, 62010.0],
         [0.0, 13562.0, 17828.0],
     ];
-    for (neighbors, indices, distances_squared) in izip!(
+    for (neighbors, indices, distances) in izip!(
         (&nearest_neighbors).into_iter(),
         indices.iter(),
-        distances_squared.iter()
+        distances.iter()
     ) {
-        for (neighbor, &index, distance_squared) in izip!(neighbors, indices, distances_squared) {
+        for (neighbor, &index, distance) in izip!(neighbors, indices, distances) {
             assert_eq!(neighbor.index, index);
-            assert_approx_eq!(neighbor.distance_squared, distance_squared);
+            assert_approx_eq!(neighbor.distance, distance);
         }
     }
 }
@@ -266,21 +266,19 @@ fn nearest_neighbors_returns_correct_item() {
 fn nearest_neighbors_get_truncated() {
     type Point2 = VecIndex<f32>;
     let data = vec![vec![0.0, 0.0], vec![1.0, 1.0], vec![2.0, 2.0]];
-    let mut index = Point2::new(2, vec![vec![0.0; 2]], Parameters::default()).unwrap();
+    let index = Point2::new(2, vec![vec![0.0; 2]], Parameters::default()).unwrap();
     let res = index.find_many_nearest_neighbors(4, data).unwrap();
-    let mut res = (&res).into_iter();
-
-    let mut first = res.next().unwrap();
-    assert_eq!(first.next().unwrap().index, 0);
-    assert!(first.next().is_none());
-    assert_eq!(res.next().unwrap().next().unwrap().index, 0);
-    assert_eq!(res.next().unwrap().next().unwrap().index, 0);
-    assert!(res.next().is_none());
+    assert_eq!(res.len(), 3);
+    assert_eq!(res.k(), 1);
+    for neighbors in &res {
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].index, 0);
+    }
 }
 
 #[test]
 fn search_radius_returns_correct_item() {
-    let mut index: VecIndex<f32> = VecIndex::new(
+    let index: VecIndex<f32> = VecIndex::new(
         3,
         vec![
             vec![0.0, 0.0, 0.0],
@@ -360,3 +358,609 @@ fn search_radius_returns_correct_item() {
     indices.sort();
     assert_eq!(indices, vec![0, 1, 2, 3, 4, 5, 6, 7]);
 }
+
+#[test]
+fn saves_and_loads() {
+    let points = vec![
+        vec![0.0, 0.0, 0.0],
+        vec![0.0, 0.0, 1.0],
+        vec![0.0, 1.0, 0.0],
+        vec![0.0, 1.0, 1.0],
+        vec![1.0, 0.0, 0.0],
+        vec![1.0, 0.0, 1.0],
+        vec![1.0, 1.0, 0.0],
+        vec![1.0, 1.0, 1.0],
+    ];
+    let path = std::env::temp_dir().join("flann_vec_index_saves_and_loads.idx");
+    let index: VecIndex<f32> = VecIndex::new(3, points.clone(), Parameters::default()).unwrap();
+    index.save(&path).unwrap();
+
+    let index: VecIndex<f32> =
+        VecIndex::load(&path, 3, points.clone(), Parameters::default()).unwrap();
+    assert_eq!(index.len(), 8);
+    assert_eq!(
+        index.find_nearest_neighbor(&[2.0, 2.0, 2.0]).unwrap().index,
+        7
+    );
+
+    match VecIndex::<f32>::load(&path, 3, points[..4].to_vec(), Parameters::default()) {
+        Err(FlannError::InvalidPointCount {
+            expected: 8,
+            got: 4,
+        }) => {}
+        _ => panic!("expected point count mismatch"),
+    }
+    match VecIndex::<f32>::load(&path, 2, vec![vec![0.0; 2]; 8], Parameters::default()) {
+        Err(FlannError::InvalidPointDimensionality {
+            expected: 3,
+            got: 2,
+        }) => {}
+        _ => panic!("expected dimensionality mismatch"),
+    }
+
+    let saved_algorithm = Parameters::default().algorithm;
+    let index: VecIndex<f32> = VecIndex::load(
+        &path,
+        3,
+        points.clone(),
+        Parameters {
+            algorithm: Algorithm::Saved,
+            ..Parameters::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        index.parameters().algorithm.as_raw(),
+        saved_algorithm.as_raw()
+    );
+    let linear = Parameters {
+        algorithm: Algorithm::Linear,
+        ..Parameters::default()
+    };
+    match VecIndex::<f32>::load(&path, 3, points.clone(), linear) {
+        Err(FlannError::InvalidAlgorithm { .. }) => {}
+        _ => panic!("expected algorithm mismatch"),
+    }
+    let invalid = Parameters {
+        distance_type: DistanceType::Minkowski,
+        distance_order: 0,
+        ..Parameters::default()
+    };
+    assert!(VecIndex::<f32>::load(&path, 3, points.clone(), invalid).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn saves_and_loads_bundle() {
+    let path = std::env::temp_dir().join("flann_vec_index_saves_and_loads_bundle.bundle");
+    let mut index: VecIndex<f32> =
+        VecIndex::new(3, vec![vec![0.0, 0.0, 0.0]; 4], Parameters::default()).unwrap();
+    index.add(vec![1.0, 2.0, 3.0]).unwrap();
+    index
+        .add_many(vec![vec![4.0, 5.0, 6.0], vec![7.0, 8.0, 9.0]])
+        .unwrap();
+    index.save_bundle(&path).unwrap();
+
+    let index = VecIndex::<f32>::load_bundle(&path).unwrap();
+    assert_eq!(index.len(), 7);
+    assert_eq!(index.get(5).unwrap(), &[4.0, 5.0, 6.0]);
+    assert_eq!(
+        index.find_nearest_neighbor(&[7.0, 8.0, 8.0]).unwrap().index,
+        6
+    );
+
+    match VecIndex::<u8>::load_bundle(&path) {
+        Err(FlannError::BundleDataTypeMismatch { .. }) => {}
+        _ => panic!("expected data type mismatch"),
+    }
+
+    // Version 2 bundles have no removed points after the dataset.
+    let bundle = std::fs::read(&path).unwrap();
+    let distance_offset = 124 + std::mem::size_of::<std::os::raw::c_long>() + 4;
+    let points_end = distance_offset + 8 + 7 * 3 * 4;
+    let mut v2 = bundle[..points_end].to_vec();
+    v2[8..12].copy_from_slice(&2u32.to_ne_bytes());
+    v2.extend_from_slice(&bundle[points_end + 8..]);
+    std::fs::write(&path, &v2).unwrap();
+    let index = VecIndex::<f32>::load_bundle(&path).unwrap();
+    assert_eq!(index.len(), 7);
+    assert_eq!(index.live_len(), 7);
+
+    // Version 1 bundles also end the parameters before the distance type and order.
+    let mut v1 = v2[..distance_offset].to_vec();
+    v1[8..12].copy_from_slice(&1u32.to_ne_bytes());
+    v1.extend_from_slice(&v2[distance_offset + 8..]);
+    std::fs::write(&path, v1).unwrap();
+    let index = VecIndex::<f32>::load_bundle(&path).unwrap();
+    assert_eq!(index.len(), 7);
+    assert!(matches!(
+        index.parameters().distance_type,
+        DistanceType::Euclidean
+    ));
+
+    std::fs::write(&path, b"not a bundle at all").unwrap();
+    match VecIndex::<f32>::load_bundle(&path) {
+        Err(FlannError::InvalidBundle) => {}
+        _ => panic!("expected invalid bundle"),
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn bundle_keeps_removed_points() {
+    let path = std::env::temp_dir().join("flann_vec_index_bundle_keeps_removed_points.bundle");
+    let mut index: VecIndex<f32> =
+        VecIndex::new(1, (0..5).map(|i| vec![i as f32]), Parameters::default()).unwrap();
+    index.remove(1).unwrap();
+    index.remove(3).unwrap();
+    index.save_bundle(&path).unwrap();
+
+    let mut index = VecIndex::<f32>::load_bundle(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(index.live_len(), 3);
+    assert_eq!(index.removed_len(), 2);
+    assert_eq!(index.get(1), None);
+    let ids = index.iter().map(|(idx, _)| idx).collect::<Vec<_>>();
+    assert_eq!(ids, vec![0, 2, 4]);
+    assert_eq!(index.find_nearest_neighbor(&[2.9]).unwrap().index, 2);
+    let remap = index.compact().unwrap();
+    assert_eq!(remap, vec![Some(0), None, Some(1), None, Some(2)]);
+    assert_eq!(index.live_len(), 3);
+}
+
+#[test]
+fn search_with_params_overrides_checks() {
+    let index: VecIndex<f32> = VecIndex::new(
+        2,
+        (0..100).map(|i| vec![i as f32, (i * i % 17) as f32]),
+        Parameters::default(),
+    )
+    .unwrap();
+    let params = SearchParams {
+        checks: Checks::Unlimited,
+        ..SearchParams::default()
+    };
+
+    let neighbors = index
+        .find_nearest_neighbors_with_params(3, &[50.2, 1.0], &params)
+        .unwrap()
+        .map(|n| n.index)
+        .collect::<Vec<usize>>();
+    assert_eq!(neighbors.len(), 3);
+    assert_eq!(neighbors[0], 50);
+    assert_eq!(
+        index
+            .find_nearest_neighbors_radius_with_params(10, 0.5, &[50.2, 1.0], &params)
+            .unwrap()
+            .map(|n| n.index)
+            .collect::<Vec<usize>>(),
+        vec![50]
+    );
+    let many = index
+        .find_many_nearest_neighbors_flat_with_params(1, &[3.0, 9.0, 4.0, 16.0], &params)
+        .unwrap();
+    let indices = many
+        .iter()
+        .map(|neighbors| neighbors[0].index)
+        .collect::<Vec<usize>>();
+    assert_eq!(indices, vec![3, 4]);
+}
+
+#[test]
+fn searches_concurrently() {
+    let index: VecIndex<f32> = VecIndex::new(
+        2,
+        (0..100).map(|i| vec![i as f32, 0.0]),
+        Parameters::default(),
+    )
+    .unwrap();
+    let index = std::sync::Arc::new(index);
+    let handles = (0..4)
+        .map(|thread| {
+            let index = index.clone();
+            std::thread::spawn(move || {
+                for i in (thread..100).step_by(4) {
+                    let neighbor = index.find_nearest_neighbor(&[i as f32, 0.1]).unwrap();
+                    assert_eq!(neighbor.index, i);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_search_matches_serial_search() {
+    let index: VecIndex<f32> = VecIndex::new(
+        2,
+        (0..1000).map(|i| vec![(i % 37) as f32, (i % 101) as f32]),
+        Parameters::default(),
+    )
+    .unwrap();
+    let queries = (0..500)
+        .flat_map(|i| vec![(i % 41) as f32 + 0.3, (i % 97) as f32 - 0.2])
+        .collect::<Vec<f32>>();
+
+    let parallel = index.par_find_many_nearest_neighbors(3, &queries).unwrap();
+    let serial = index.find_many_nearest_neighbors_flat(3, &queries).unwrap();
+    assert_eq!(parallel.len(), 500);
+    for (parallel, serial) in parallel.iter().zip(&serial) {
+        for (p, s) in parallel.iter().zip(serial) {
+            assert_eq!(p.index, s.index);
+            assert_approx_eq!(p.distance, s.distance);
+        }
+    }
+}
+
+#[test]
+fn removed_points_are_not_found() {
+    let mut index: VecIndex<f32> = VecIndex::new(
+        1,
+        vec![vec![0.0], vec![1.0], vec![2.0]],
+        Parameters::default(),
+    )
+    .unwrap();
+    index.remove(1).unwrap();
+    let neighbor = index.find_nearest_neighbor(&[1.1]).unwrap();
+    assert_eq!(neighbor.index, 2);
+}
+
+#[test]
+fn tracks_and_compacts_removed_points() {
+    let mut index: VecIndex<f32> =
+        VecIndex::new(1, (0..6).map(|i| vec![i as f32]), Parameters::default()).unwrap();
+    index.remove(1).unwrap();
+    index.remove(4).unwrap();
+    index.remove(4).unwrap();
+    assert_eq!(index.live_len(), 4);
+    assert_eq!(index.removed_len(), 2);
+    assert_eq!(index.get(1), None);
+    let live = index
+        .iter()
+        .map(|(idx, point)| (idx, point[0]))
+        .collect::<Vec<_>>();
+    assert_eq!(live, vec![(0, 0.0), (2, 2.0), (3, 3.0), (5, 5.0)]);
+
+    let remap = index.compact().unwrap();
+    assert_eq!(remap, vec![Some(0), None, Some(1), Some(2), None, Some(3)]);
+    assert_eq!(index.live_len(), 4);
+    assert_eq!(index.removed_len(), 0);
+    assert_eq!(index.get(3), Some(&[5.0][..]));
+    assert_eq!(index.find_nearest_neighbor(&[4.2]).unwrap().index, 2);
+    index.add(vec![10.0]).unwrap();
+    assert_eq!(index.get(4), Some(&[10.0][..]));
+}
+
+#[test]
+fn compacts_slice_index() {
+    let points = (0..10).map(|i| i as f32).collect::<Vec<_>>();
+    let mut index = SliceIndex::new(2, &points[..], Parameters::default()).unwrap();
+    index.remove(0).unwrap();
+    let (compacted, remap) = index.compact().unwrap();
+    assert_eq!(remap, vec![None, Some(0), Some(1), Some(2), Some(3)]);
+    assert_eq!(compacted.len(), 4);
+    assert_eq!(compacted.get(0), Some(&[2.0, 3.0][..]));
+    drop(index);
+    assert_eq!(compacted.get(3), Some(&[8.0, 9.0][..]));
+}
+
+#[test]
+fn finds_all_points_within_radius() {
+    let index: VecIndex<f32> = VecIndex::new(
+        1,
+        (0..100).map(|i| vec![i as f32 * 0.1]),
+        Parameters::default(),
+    )
+    .unwrap();
+    let all = index.find_all_within_radius(1.05, &[5.0]).unwrap();
+    assert_eq!(all.len(), 21);
+    assert_eq!(all[0].index, 50);
+    assert!(all.windows(2).all(|w| w[0].distance <= w[1].distance));
+    assert!(index
+        .find_all_within_radius(1.0, &[100.0])
+        .unwrap()
+        .is_empty());
+
+    let bounded = index.find_within_radius(5, 1.05, &[5.0]).unwrap();
+    assert!(bounded.truncated);
+    assert_eq!(bounded.neighbors.len(), 5);
+    assert_eq!(bounded.neighbors[0].index, 50);
+    let bounded = index.find_within_radius(21, 1.05, &[5.0]).unwrap();
+    assert!(!bounded.truncated);
+    assert_eq!(bounded.neighbors.len(), 21);
+}
+
+#[test]
+fn finds_points_within_radius_of_many_queries() {
+    let index: VecIndex<f32> =
+        VecIndex::new(1, (0..10).map(|i| vec![i as f32]), Parameters::default()).unwrap();
+    let queries = [0.0, 4.5, 20.0];
+    let neighbors = index.find_many_within_radius_flat(1.5, &queries).unwrap();
+    assert_eq!(neighbors.len(), 3);
+    assert_eq!(neighbors.offsets, vec![0, 2, 4, 4]);
+    assert_eq!(neighbors.get(0).unwrap().0, &[0, 1]);
+    let (indices, distances) = neighbors.get(1).unwrap();
+    assert_eq!(indices.len(), 2);
+    assert_eq!(distances, &[0.25, 0.25]);
+    assert!(neighbors.get(3).is_none());
+
+    let radii = [0.5, 5.0, 1.5];
+    let neighbors = index
+        .find_many_within_radius(&radii[..], vec![vec![0.0], vec![4.5], vec![9.0]])
+        .unwrap();
+    let counts = neighbors
+        .iter()
+        .map(|(indices, _)| indices.len())
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![1, 4, 2]);
+
+    match index.find_many_within_radius_flat(&radii[..2], &queries) {
+        Err(FlannError::InvalidParameter {
+            parameter: "radii", ..
+        }) => {}
+        _ => panic!("expected invalid radii error"),
+    }
+}
+
+#[test]
+fn radius_search_grows_buffers_for_dense_queries() {
+    let index: VecIndex<f32> = VecIndex::new(
+        1,
+        (0..1000).map(|i| vec![i as f32]),
+        Parameters {
+            algorithm: Algorithm::Linear,
+            ..Parameters::default()
+        },
+    )
+    .unwrap();
+    // Squared distances below 100^2 are within 99 of the query on either side.
+    let all = index.find_all_within_radius(10_000.0, &[500.0]).unwrap();
+    assert_eq!(all.len(), 199);
+    let everything = index.find_all_within_radius(f32::INFINITY, &[0.0]).unwrap();
+    assert_eq!(everything.len(), 1000);
+
+    // Sparse, dense and sparse again reuse the same buffers.
+    let radii = [0.5, 10_000.0, 0.5, 4.0];
+    let neighbors = index
+        .find_many_within_radius_flat(&radii[..], &[3.0, 500.0, 7.0, 999.0])
+        .unwrap();
+    let counts = neighbors
+        .iter()
+        .map(|(indices, _)| indices.len())
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![1, 199, 1, 2]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_radius_search_matches_serial() {
+    let index: VecIndex<f32> = VecIndex::new(
+        2,
+        (0..500).map(|i| vec![(i % 25) as f32, (i / 25) as f32]),
+        Parameters::default(),
+    )
+    .unwrap();
+    let queries = (0..200)
+        .flat_map(|i| vec![(i % 17) as f32 + 0.5, (i % 13) as f32])
+        .collect::<Vec<_>>();
+    let serial = index.find_many_within_radius_flat(4.0, &queries).unwrap();
+    let parallel = index
+        .par_find_many_within_radius_flat(4.0, &queries)
+        .unwrap();
+    assert_eq!(serial.offsets, parallel.offsets);
+    assert_eq!(serial.indices, parallel.indices);
+}
+
+fn distance_parameters(distance_type: DistanceType, distance_order: i32) -> Parameters {
+    Parameters {
+        algorithm: Algorithm::Linear,
+        distance_type,
+        distance_order,
+        ..Parameters::default()
+    }
+}
+
+#[test]
+fn reports_distances_in_chosen_metric() {
+    let points = vec![vec![0.0, 0.0], vec![3.0, 4.0]];
+    let query = [0.0f32, 0.0];
+    let expected = [
+        (DistanceType::Euclidean, 0, 25.0),
+        (DistanceType::Manhattan, 0, 7.0),
+        (DistanceType::Minkowski, 3, 91.0),
+    ];
+    for &(distance_type, order, distance) in &expected {
+        let index: VecIndex<f32> =
+            VecIndex::new(2, points.clone(), distance_parameters(distance_type, order)).unwrap();
+        let neighbors = index
+            .find_nearest_neighbors(2, &query)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(neighbors[1].index, 1);
+        assert_approx_eq!(neighbors[1].distance, distance);
+    }
+}
+
+#[test]
+fn rejects_unsupported_distance() {
+    match VecIndex::<f32>::new(
+        1,
+        vec![vec![0.0]],
+        distance_parameters(DistanceType::Max, 0),
+    ) {
+        Err(FlannError::UnsupportedDistanceType { .. }) => {}
+        _ => panic!("expected unsupported distance type error"),
+    }
+}
+
+#[test]
+fn rejects_minkowski_order_below_one() {
+    match VecIndex::<f32>::new(
+        1,
+        vec![vec![0.0]],
+        distance_parameters(DistanceType::Minkowski, 0),
+    ) {
+        Err(FlannError::InvalidParameter {
+            parameter: "distance_order",
+            ..
+        }) => {}
+        _ => panic!("expected invalid distance order error"),
+    }
+}
+
+#[test]
+fn indices_with_different_distances_search_concurrently() {
+    let handles = [DistanceType::Euclidean, DistanceType::Manhattan]
+        .iter()
+        .map(|&distance_type| {
+            std::thread::spawn(move || {
+                let index: VecIndex<f32> = VecIndex::new(
+                    2,
+                    vec![vec![0.0, 0.0], vec![3.0, 4.0]],
+                    distance_parameters(distance_type, 0),
+                )
+                .unwrap();
+                let expected = match distance_type {
+                    DistanceType::Manhattan => 7.0,
+                    _ => 25.0,
+                };
+                for _ in 0..1000 {
+                    let neighbor = index.find_nearest_neighbor(&[3.0, 4.0]).unwrap();
+                    assert_eq!(neighbor.index, 1);
+                    let neighbors = index
+                        .find_nearest_neighbors(2, &[0.0, 0.0])
+                        .unwrap()
+                        .collect::<Vec<_>>();
+                    assert_approx_eq!(neighbors[1].distance, expected);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn reports_autotuned_build() {
+    let points = (0..1000)
+        .map(|i| vec![(i % 37) as f32, (i % 101) as f32])
+        .collect::<Vec<_>>();
+    let index: VecIndex<f32> = VecIndex::new(
+        2,
+        points.clone(),
+        Parameters {
+            algorithm: Algorithm::Autotuned,
+            ..Parameters::default()
+        },
+    )
+    .unwrap();
+    let report = index.build_report().unwrap().clone();
+    assert!(report.speedup.unwrap() > 0.0);
+    assert!(!matches!(report.algorithm, Algorithm::Autotuned));
+
+    // The tuned parameters build the same kind of index without autotuning again.
+    let rebuilt: VecIndex<f32> = VecIndex::new(2, points, report.parameters).unwrap();
+    let rebuilt_report = rebuilt.build_report().unwrap();
+    assert!(rebuilt_report.speedup.is_none());
+    assert_eq!(rebuilt_report.algorithm.as_raw(), report.algorithm.as_raw());
+}
+
+#[test]
+fn loaded_index_has_no_build_report() {
+    let index: VecIndex<f32> =
+        VecIndex::new(1, vec![vec![0.0], vec![1.0]], Parameters::default()).unwrap();
+    let report = index.build_report().unwrap();
+    assert!(report.speedup.is_none());
+    assert_eq!(report.algorithm.as_raw(), Algorithm::KDTree.as_raw());
+
+    let path = std::env::temp_dir().join(format!("flann-report-{}.bundle", std::process::id()));
+    index.save_bundle(&path).unwrap();
+    let loaded = VecIndex::<f32>::load_bundle(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(loaded.build_report().is_none());
+}
+
+#[test]
+fn knn_search_into_matches_find_many() {
+    let index: VecIndex<f32> = VecIndex::new(
+        2,
+        (0..50).map(|i| vec![i as f32, 0.0]),
+        Parameters::default(),
+    )
+    .unwrap();
+    let points = [3.1, 0.0, 40.2, 0.0, -5.0, 0.0];
+    let mut indices = vec![0; 9];
+    let mut distances = vec![0.0; 9];
+    let found = index
+        .knn_search_into(&points, 3, &mut indices, &mut distances)
+        .unwrap();
+    assert_eq!(found, 3);
+    let expected = index.find_many_nearest_neighbors_flat(3, &points).unwrap();
+    let expected = expected.neighbors();
+    for (i, neighbor) in expected.iter().enumerate() {
+        assert_eq!(indices[i], neighbor.index);
+        assert_approx_eq!(distances[i], neighbor.distance);
+    }
+    assert_eq!(&indices[..3], &[3, 4, 2]);
+
+    // Buffers must have room for `k` neighbors of every point.
+    assert!(index
+        .knn_search_into(&points, 4, &mut indices, &mut distances)
+        .is_err());
+}
+
+#[test]
+fn knn_search_into_buffers_reuses_memory() {
+    let index: VecIndex<f32> =
+        VecIndex::new(1, (0..5).map(|i| vec![i as f32]), Parameters::default()).unwrap();
+    let mut buffers = SearchBuffers::with_capacity(20);
+    index
+        .knn_search_into_buffers(&[0.9, 3.2], 2, &mut buffers)
+        .unwrap();
+    assert_eq!(buffers.len(), 2);
+    assert_eq!(buffers.num_neighbors(), 2);
+    assert_eq!(buffers.get(0).unwrap().0, &[1, 0]);
+    assert_eq!(buffers.get(1).unwrap().0, &[3, 4]);
+    assert!(buffers.get(2).is_none());
+
+    // More neighbors than points finds every point.
+    index
+        .knn_search_into_buffers(&[4.0], 10, &mut buffers)
+        .unwrap();
+    assert_eq!(buffers.len(), 1);
+    assert_eq!(buffers.num_neighbors(), 5);
+    let (indices, distances) = buffers.iter().next().unwrap();
+    assert_eq!(indices, &[4, 3, 2, 1, 0]);
+    assert_approx_eq!(distances[4], 16.0);
+}
+
+#[test]
+fn many_search_handles_zero_neighbors_and_queries() {
+    let index: VecIndex<f32> =
+        VecIndex::new(1, (0..5).map(|i| vec![i as f32]), Parameters::default()).unwrap();
+    let none = index
+        .find_many_nearest_neighbors_flat(0, &[1.0, 2.0])
+        .unwrap();
+    assert_eq!(none.len(), 2);
+    assert_eq!(none.k(), 0);
+    assert!(none.row(1).is_empty());
+    let vecs = none.into_vecs();
+    assert_eq!(vecs.len(), 2);
+    assert!(vecs.iter().all(Vec::is_empty));
+
+    let empty = index.find_many_nearest_neighbors_flat(3, &[]).unwrap();
+    assert!(empty.is_empty());
+    assert!(empty.get(0).is_none());
+
+    let neighbors = index
+        .find_many_nearest_neighbors_flat(2, &[0.9, 3.8])
+        .unwrap();
+    let vecs: Vec<Vec<Neighbor<f32>>> = neighbors.clone().into();
+    assert_eq!(vecs.len(), 2);
+    assert_eq!(vecs[1][0].index, neighbors.row(1)[0].index);
+    assert_eq!(neighbors.row(1)[0].index, 4);
+}
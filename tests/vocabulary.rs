@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate assert_approx_eq;
+extern crate flann;
+
+use flann::*;
+
+fn descriptors() -> Vec<u8> {
+    vec![0, 0, 0, 1, 1, 0, 200, 200, 200, 201, 201, 200]
+}
+
+fn vocabulary() -> Vocabulary<u8> {
+    let parameters = Parameters {
+        branching: 2,
+        ..Parameters::default()
+    };
+    Vocabulary::new(2, &descriptors(), 2, parameters).unwrap()
+}
+
+#[test]
+fn quantizes_to_closest_word() {
+    let vocabulary = vocabulary();
+    assert_eq!(vocabulary.len(), 2);
+    let words = vocabulary.quantize(&[1, 1, 199, 199, 0, 1]).unwrap();
+    assert_eq!(words[0], words[2]);
+    assert_ne!(words[0], words[1]);
+    assert!(vocabulary.word(words[1]).unwrap()[0] > 100.0);
+}
+
+#[test]
+fn histogram_is_normalized() {
+    let vocabulary = vocabulary();
+    let histogram = vocabulary.histogram(&[1, 1, 199, 199, 0, 1, 2, 2]).unwrap();
+    let low = vocabulary.quantize(&[0, 0]).unwrap()[0];
+    assert_approx_eq!(histogram[low], 0.75);
+    assert_approx_eq!(histogram[1 - low], 0.25);
+}
+
+#[test]
+fn idf_weights_rare_words() {
+    let mut vocabulary = vocabulary();
+    let documents: [&[u8]; 2] = [&[0, 0, 1, 1], &[0, 0, 200, 200]];
+    vocabulary.compute_idf(documents.iter().cloned()).unwrap();
+    let low = vocabulary.quantize(&[0, 0]).unwrap()[0];
+    let idf = vocabulary.idf().unwrap();
+    assert_approx_eq!(idf[low], 0.0);
+    assert_approx_eq!(idf[1 - low], 2.0f32.ln());
+
+    let histogram = vocabulary.histogram(&[0, 0, 200, 200]).unwrap();
+    assert_approx_eq!(histogram[low], 0.0);
+    assert_approx_eq!(histogram[1 - low], 0.5 * 2.0f32.ln());
+}
+
+#[test]
+fn saves_and_loads() {
+    let mut vocabulary = vocabulary();
+    let documents: [&[u8]; 2] = [&[0, 0], &[200, 200]];
+    vocabulary.compute_idf(documents.iter().cloned()).unwrap();
+    let path = std::env::temp_dir().join(format!("flann-vocabulary-{}.voc", std::process::id()));
+    vocabulary.save(&path).unwrap();
+    let loaded = Vocabulary::<u8>::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.len(), vocabulary.len());
+    assert_eq!(loaded.idf(), vocabulary.idf());
+    let query = descriptors();
+    assert_eq!(
+        loaded.quantize(&query).unwrap(),
+        vocabulary.quantize(&query).unwrap()
+    );
+}